@@ -1,124 +1,396 @@
+use std::{cmp::Reverse, str::FromStr};
+
+use anyhow::anyhow;
+
+use aho_corasick::AhoCorasick;
+
+mod aho_corasick;
+
 const INPUT: &'static str = include_str!("../../inputs/2023/day_01.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u32> {
-	Ok(solve(INPUT.lines(), part_two))
+	let vocabulary = match part_two {
+		false => Vocabulary::digits(),
+		true => crate::option(
+			"Vocabulary: english, english-tens, german, french or word=value, comma separated (english)",
+		)?
+		.unwrap_or(Vocabulary::english()),
+	};
+
+	if crate::option("Print diagnostics? (false)")?.unwrap_or(false) {
+		let matcher = NumberMatcher::new(&vocabulary);
+		for (i, line) in INPUT.lines().enumerate() {
+			println!("line {}: {}", i + 1, matcher.diagnose(line));
+		}
+	}
+
+	solve(INPUT.lines(), &vocabulary)
 }
 
-fn solve(lines: impl IntoIterator<Item = &'static str>, include_words: bool) -> u32 {
+fn solve(
+	lines: impl IntoIterator<Item = &'static str>,
+	vocabulary: &Vocabulary,
+) -> anyhow::Result<u32> {
+	let matcher = NumberMatcher::new(vocabulary);
 	lines
 		.into_iter()
-		.map(|line| extract_numbers(line, include_words))
-		.map(|numbers| calibration_value(&numbers))
+		.enumerate()
+		.map(|(i, line)| {
+			let (first, last) = matcher
+				.first_and_last(line)
+				.ok_or(anyhow!("line {} has no numbers: \"{line}\"", i + 1))?;
+			Ok(calibration_value(first.value, last.value))
+		})
 		.sum()
 }
 
-struct NumberPattern {
-	pattern: &'static str,
-	value: u32,
-}
+const ENGLISH: [(&str, u32); 9] = [
+	("one", 1),
+	("two", 2),
+	("three", 3),
+	("four", 4),
+	("five", 5),
+	("six", 6),
+	("seven", 7),
+	("eight", 8),
+	("nine", 9),
+];
+
+const ENGLISH_TENS: [(&str, u32); 18] = [
+	("ten", 10),
+	("eleven", 11),
+	("twelve", 12),
+	("thirteen", 13),
+	("fourteen", 14),
+	("fifteen", 15),
+	("sixteen", 16),
+	("seventeen", 17),
+	("eighteen", 18),
+	("nineteen", 19),
+	("twenty", 20),
+	("thirty", 30),
+	("forty", 40),
+	("fifty", 50),
+	("sixty", 60),
+	("seventy", 70),
+	("eighty", 80),
+	("ninety", 90),
+];
 
-const NUMBER_PATTERNS: [NumberPattern; 9] = [
-	NumberPattern {
-		pattern: "one",
-		value: 1,
-	},
-	NumberPattern {
-		pattern: "two",
-		value: 2,
-	},
-	NumberPattern {
-		pattern: "three",
-		value: 3,
-	},
-	NumberPattern {
-		pattern: "four",
-		value: 4,
-	},
-	NumberPattern {
-		pattern: "five",
-		value: 5,
-	},
-	NumberPattern {
-		pattern: "six",
-		value: 6,
-	},
-	NumberPattern {
-		pattern: "seven",
-		value: 7,
-	},
-	NumberPattern {
-		pattern: "eight",
-		value: 8,
-	},
-	NumberPattern {
-		pattern: "nine",
-		value: 9,
-	},
+const GERMAN: [(&str, u32); 9] = [
+	("eins", 1),
+	("zwei", 2),
+	("drei", 3),
+	("vier", 4),
+	("fünf", 5),
+	("sechs", 6),
+	("sieben", 7),
+	("acht", 8),
+	("neun", 9),
 ];
 
-struct Matcher {
-	pattern: &'static NumberPattern,
-	pointer: usize,
+const FRENCH: [(&str, u32); 9] = [
+	("un", 1),
+	("deux", 2),
+	("trois", 3),
+	("quatre", 4),
+	("cinq", 5),
+	("six", 6),
+	("sept", 7),
+	("huit", 8),
+	("neuf", 9),
+];
+
+/// The words that count as numbers, next to the digits themselves
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Vocabulary {
+	words: Vec<(String, u32)>,
 }
 
-fn calibration_value(numbers: &[u32]) -> u32 {
-	numbers.first().unwrap() * 10 + numbers.last().unwrap()
+impl Vocabulary {
+	fn digits() -> Self {
+		Vocabulary {
+			words: (0..=9).map(|digit| (digit.to_string(), digit)).collect(),
+		}
+	}
+
+	fn english() -> Self {
+		Vocabulary::digits().with(ENGLISH)
+	}
+
+	fn with<'a>(mut self, words: impl IntoIterator<Item = (&'a str, u32)>) -> Self {
+		self.words.extend(
+			words
+				.into_iter()
+				.map(|(word, value)| (word.to_string(), value)),
+		);
+		self
+	}
 }
 
-fn extract_numbers(input: &str, include_words: bool) -> Vec<u32> {
-	let mut numbers = Vec::<u32>::new();
-	let mut matchers = Vec::<Matcher>::new();
-	for char in input.chars() {
-		match char {
-			'0'..='9' => numbers.push(char.to_digit(10).unwrap()),
-			_ => {
-				if !include_words {
-					continue;
-				}
-
-				// remove invalid matchers
-				matchers.retain(|matcher| {
-					match matcher.pattern.pattern.chars().nth(matcher.pointer) {
-						Some(c) => c == char,
-						None => false,
-					}
-				});
+/// Parses a comma separated list of languages and `word=value` pairs, which are added to the
+/// digits.
+impl FromStr for Vocabulary {
+	type Err = anyhow::Error;
 
-				// advance remaining matchers
-				matchers.iter_mut().for_each(|matcher| {
-					matcher.pointer += 1;
-					if matcher.pointer == matcher.pattern.pattern.len() {
-						numbers.push(matcher.pattern.value);
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(',')
+			.map(str::trim)
+			.try_fold(Vocabulary::digits(), |vocabulary, item| {
+				Ok(match item {
+					"english" => vocabulary.with(ENGLISH),
+					"english-tens" => vocabulary.with(ENGLISH).with(ENGLISH_TENS),
+					"german" => vocabulary.with(GERMAN),
+					"french" => vocabulary.with(FRENCH),
+					_ => {
+						let (word, value) = item
+							.split_once('=')
+							.ok_or(anyhow!("unknown language \"{item}\""))?;
+						if word.is_empty() {
+							return Err(anyhow!("empty word in \"{item}\""));
+						}
+						vocabulary.with([(word, value.parse()?)])
 					}
-				});
-
-				// add new matchers
-				NUMBER_PATTERNS
-					.iter()
-					.filter(|np| np.pattern.starts_with(char))
-					.for_each(|np| {
-						matchers.push(Matcher {
-							pattern: np,
-							pointer: 1,
-						})
-					});
-			}
+				})
+			})
+	}
+}
+
+/// A number found in a line, with the byte range it was written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Number {
+	value: u32,
+	start: usize,
+	end: usize,
+	source: Source,
+}
+
+/// Whether a number was written with digits or spelled out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+	Digit,
+	Word,
+}
+
+/// Finds the numbers of a vocabulary in a line, with one automaton reading forwards and one reading
+/// the reversed words backwards.
+struct NumberMatcher {
+	forward: AhoCorasick,
+	backward: AhoCorasick,
+	values: Vec<u32>,
+	sources: Vec<Source>,
+}
+
+impl NumberMatcher {
+	fn new(vocabulary: &Vocabulary) -> Self {
+		let words = vocabulary.words.iter().map(|(word, _)| word.as_bytes());
+		NumberMatcher {
+			forward: AhoCorasick::new(words.clone()),
+			backward: AhoCorasick::new(
+				words.map(|word| word.iter().rev().copied().collect::<Vec<_>>()),
+			),
+			values: vocabulary.words.iter().map(|&(_, value)| value).collect(),
+			sources: vocabulary
+				.words
+				.iter()
+				.map(
+					|(word, _)| match word.bytes().all(|byte| byte.is_ascii_digit()) {
+						true => Source::Digit,
+						false => Source::Word,
+					},
+				)
+				.collect(),
 		}
 	}
-	numbers
+
+	fn number(&self, pattern: usize, start: usize, end: usize) -> Number {
+		Number {
+			value: self.values[pattern],
+			start,
+			end,
+			source: self.sources[pattern],
+		}
+	}
+
+	/// Returns every number in the line, including overlapping ones like both numbers in
+	/// "eightwo". They are ordered by where they start, longer numbers first.
+	fn numbers(&self, line: &str) -> Vec<Number> {
+		let mut numbers = self
+			.forward
+			.find_overlapping(line.bytes())
+			.map(|m| self.number(m.pattern, m.start, m.end))
+			.collect::<Vec<_>>();
+		numbers.sort_unstable_by_key(|number| (number.start, Reverse(number.end)));
+		numbers
+	}
+
+	/// Returns the number that starts first and the number that ends last, preferring longer
+	/// numbers on ties. Only scans the line from both ends until they are found.
+	fn first_and_last(&self, line: &str) -> Option<(Number, Number)> {
+		let first = self.forward.find_leftmost_longest(line.bytes())?;
+		// this cannot panic because `first` is also found backwards
+		let last = self
+			.backward
+			.find_leftmost_longest(line.bytes().rev())
+			.unwrap();
+		Some((
+			self.number(first.pattern, first.start, first.end),
+			self.number(last.pattern, line.len() - last.end, line.len() - last.start),
+		))
+	}
+
+	/// Describes every number in the line with its position and source, followed by the
+	/// calibration value, e.g. `xtwone3: two at 1..4 (word), one at 3..6 (word), 3 at 6..7
+	/// (digit) => 23`.
+	fn diagnose(&self, line: &str) -> String {
+		let numbers = self
+			.numbers(line)
+			.iter()
+			.map(|number| {
+				let source = match number.source {
+					Source::Digit => "digit",
+					Source::Word => "word",
+				};
+				format!(
+					"{} at {}..{} ({source})",
+					&line[number.start..number.end],
+					number.start,
+					number.end
+				)
+			})
+			.collect::<Vec<_>>();
+		let result = match self.first_and_last(line) {
+			Some((first, last)) => calibration_value(first.value, last.value).to_string(),
+			None => "no numbers".to_string(),
+		};
+		match numbers.is_empty() {
+			true => format!("{line}: {result}"),
+			false => format!("{line}: {} => {result}", numbers.join(", ")),
+		}
+	}
+}
+
+/// Combines the leading digit of the first number with the trailing digit of the last.
+fn calibration_value(first: u32, last: u32) -> u32 {
+	let mut leading = first;
+	while leading >= 10 {
+		leading /= 10;
+	}
+	leading * 10 + last % 10
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_vocabulary_from_str() {
+		assert_eq!(
+			"english".parse::<Vocabulary>().unwrap(),
+			Vocabulary::english()
+		);
+		assert_eq!(
+			"german, zero=0".parse::<Vocabulary>().unwrap(),
+			Vocabulary::digits().with(GERMAN).with([("zero", 0)])
+		);
+		assert!("klingon".parse::<Vocabulary>().is_err());
+		assert!("=1".parse::<Vocabulary>().is_err());
+		assert!("ten=x".parse::<Vocabulary>().is_err());
+	}
+
+	#[test]
+	fn test_numbers_overlapping() {
+		let matcher = NumberMatcher::new(&Vocabulary::english());
+		let values = matcher
+			.numbers("eightwo3oneight")
+			.iter()
+			.map(|number| number.value)
+			.collect::<Vec<_>>();
+		assert_eq!(values, [8, 2, 3, 1, 8]);
+	}
+
+	#[test]
+	fn test_digits_interrupt_words() {
+		let matcher = NumberMatcher::new(&Vocabulary::english());
+		assert_eq!(matcher.numbers("on1e").len(), 1);
+	}
+
+	#[test]
+	fn test_other_languages() {
+		let vocabulary = "german,french".parse::<Vocabulary>().unwrap();
+		assert_eq!(
+			solve(["xfünfabc", "achtneuf"], &vocabulary).unwrap(),
+			55 + 89
+		);
+	}
+
+	#[test]
+	fn test_tens() {
+		let vocabulary = "english-tens".parse::<Vocabulary>().unwrap();
+		// "seventeen" starts where "seven" does but is longer, and ends last
+		assert_eq!(solve(["seventeen"], &vocabulary).unwrap(), 17);
+		assert_eq!(solve(["twentyx"], &vocabulary).unwrap(), 20);
+		assert_eq!(solve(["ninety4eighteen"], &vocabulary).unwrap(), 98);
+	}
+
+	#[test]
+	fn test_line_without_numbers() {
+		let err = solve(["1abc2", "abc", "3"], &Vocabulary::digits()).unwrap_err();
+		assert_eq!(err.to_string(), "line 2 has no numbers: \"abc\"");
+		assert!(solve(["one"], &Vocabulary::digits()).is_err());
+	}
+
+	#[test]
+	fn test_diagnose() {
+		let matcher = NumberMatcher::new(&Vocabulary::english());
+		assert_eq!(
+			matcher.diagnose("xtwone3"),
+			"xtwone3: two at 1..4 (word), one at 3..6 (word), 3 at 6..7 (digit) => 23"
+		);
+		assert_eq!(
+			matcher.diagnose("ssseven"),
+			"ssseven: seven at 2..7 (word) => 77"
+		);
+		assert_eq!(
+			matcher.diagnose("threee"),
+			"threee: three at 0..5 (word) => 33"
+		);
+		assert_eq!(matcher.diagnose("abc"), "abc: no numbers");
+	}
+
+	#[test]
+	fn test_calibration_value() {
+		assert_eq!(calibration_value(3, 8), 38);
+		assert_eq!(calibration_value(17, 20), 10);
+		assert_eq!(calibration_value(0, 0), 0);
+	}
+
+	proptest::proptest! {
+		#[test]
+		fn test_first_and_last_match_all_numbers(line in "[a-z0-9]{0,5}(one|two|ten|teen|seven|eigh|t|n|e|x|1){0,12}") {
+			let matcher = NumberMatcher::new(&"english-tens".parse().unwrap());
+			let numbers = matcher.numbers(&line);
+			let last = numbers
+				.iter()
+				.max_by_key(|number| (number.end, Reverse(number.start)))
+				.copied();
+			proptest::prop_assert_eq!(
+				matcher.first_and_last(&line),
+				numbers.first().copied().zip(last)
+			);
+		}
+	}
+
 	mod part_1 {
 		use super::*;
 
 		#[test]
 		fn test_example() {
 			let calibration_strings = ["1abc2", "pqr3stu8vwx", "a1b2c3d4e5f", "treb7uchet"];
-			assert_eq!(solve(calibration_strings, false), 142);
+			assert_eq!(
+				solve(calibration_strings, &Vocabulary::digits()).unwrap(),
+				142
+			);
 		}
 	}
 
@@ -136,19 +408,28 @@ mod tests {
 				"zoneight234",
 				"7pqrstsixteen",
 			];
-			assert_eq!(solve(calibration_strings, true), 281);
+			assert_eq!(
+				solve(calibration_strings, &Vocabulary::english()).unwrap(),
+				281
+			);
 		}
 
 		#[test]
 		fn test_prefix() {
 			let calibration_strings = ["ssseven"];
-			assert_eq!(solve(calibration_strings, true), 77);
+			assert_eq!(
+				solve(calibration_strings, &Vocabulary::english()).unwrap(),
+				77
+			);
 		}
 
 		#[test]
 		fn test_suffix() {
 			let calibration_strings = ["threee"];
-			assert_eq!(solve(calibration_strings, true), 33);
+			assert_eq!(
+				solve(calibration_strings, &Vocabulary::english()).unwrap(),
+				33
+			);
 		}
 	}
 }
@@ -0,0 +1,201 @@
+//! Aho-Corasick automaton that finds every occurrence of a set of byte patterns in one pass
+
+use std::{cmp::Reverse, collections::VecDeque};
+
+/// An occurrence of a pattern, given by its index and byte range in the haystack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Match {
+	pub(super) pattern: usize,
+	pub(super) start: usize,
+	pub(super) end: usize,
+}
+
+/// A deterministic automaton over bytes. Each state has a transition for every byte, so scanning
+/// never follows failure links.
+#[derive(Debug, Clone)]
+pub(super) struct AhoCorasick {
+	transitions: Vec<[usize; 256]>,
+	/// Patterns that end in each state, including those reached through failure links
+	outputs: Vec<Vec<usize>>,
+	lengths: Vec<usize>,
+}
+
+const ROOT: usize = 0;
+
+impl AhoCorasick {
+	pub(super) fn new<P: AsRef<[u8]>>(patterns: impl IntoIterator<Item = P>) -> Self {
+		let mut automaton = AhoCorasick {
+			transitions: vec![[ROOT; 256]],
+			outputs: vec![Vec::new()],
+			lengths: Vec::new(),
+		};
+
+		// build the trie, where a transition to the root means there is no edge yet
+		for (index, pattern) in patterns.into_iter().enumerate() {
+			let pattern = pattern.as_ref();
+			let mut state = ROOT;
+			for &byte in pattern {
+				state = match automaton.transitions[state][byte as usize] {
+					ROOT => {
+						automaton.transitions.push([ROOT; 256]);
+						automaton.outputs.push(Vec::new());
+						let next = automaton.transitions.len() - 1;
+						automaton.transitions[state][byte as usize] = next;
+						next
+					}
+					next => next,
+				};
+			}
+			automaton.outputs[state].push(index);
+			automaton.lengths.push(pattern.len());
+		}
+
+		// visit the states breadth first, so that the failure state of each state is complete by
+		// the time it is needed. Missing edges are replaced by the edge of the failure state.
+		let mut failures = vec![ROOT; automaton.transitions.len()];
+		let mut queue = automaton.transitions[ROOT]
+			.iter()
+			.copied()
+			.filter(|&next| next != ROOT)
+			.collect::<VecDeque<_>>();
+		while let Some(state) = queue.pop_front() {
+			let failure = failures[state];
+			let inherited = automaton.outputs[failure].clone();
+			automaton.outputs[state].extend(inherited);
+
+			for byte in 0..256 {
+				let next = automaton.transitions[state][byte];
+				let fallback = automaton.transitions[failure][byte];
+				if next == ROOT {
+					automaton.transitions[state][byte] = fallback;
+				} else {
+					failures[next] = fallback;
+					queue.push_back(next);
+				}
+			}
+		}
+
+		automaton
+	}
+
+	/// Returns every occurrence of every pattern, including overlapping ones, ordered by where
+	/// they end
+	pub(super) fn find_overlapping<'a>(
+		&'a self,
+		haystack: impl IntoIterator<Item = u8> + 'a,
+	) -> impl Iterator<Item = Match> + 'a {
+		haystack
+			.into_iter()
+			.scan(ROOT, |state, byte| {
+				*state = self.transitions[*state][byte as usize];
+				Some(*state)
+			})
+			.enumerate()
+			.flat_map(move |(i, state)| {
+				self.outputs[state].iter().map(move |&pattern| Match {
+					pattern,
+					start: i + 1 - self.lengths[pattern],
+					end: i + 1,
+				})
+			})
+	}
+
+	/// Returns the occurrence that starts first, preferring the longest pattern among those that
+	/// start at the same position. Stops scanning as soon as no later occurrence can start earlier.
+	pub(super) fn find_leftmost_longest(
+		&self,
+		haystack: impl IntoIterator<Item = u8>,
+	) -> Option<Match> {
+		let max_len = self.lengths.iter().copied().max().unwrap_or(0);
+		let mut matches = self.find_overlapping(haystack);
+		let mut best = matches.next()?;
+		for candidate in matches {
+			if candidate.end > best.start + max_len {
+				break;
+			}
+			if (candidate.start, Reverse(candidate.end)) < (best.start, Reverse(best.end)) {
+				best = candidate;
+			}
+		}
+		Some(best)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn find(patterns: &[&str], haystack: &str) -> Vec<(usize, usize, usize)> {
+		let automaton = AhoCorasick::new(patterns);
+		let mut matches = automaton
+			.find_overlapping(haystack.bytes())
+			.map(|m| (m.pattern, m.start, m.end))
+			.collect::<Vec<_>>();
+		matches.sort_unstable();
+		matches
+	}
+
+	#[test]
+	fn test_overlapping() {
+		assert_eq!(
+			find(&["one", "two", "eight"], "eightwone"),
+			[(0, 6, 9), (1, 4, 7), (2, 0, 5)]
+		);
+	}
+
+	#[test]
+	fn test_nested_patterns() {
+		// "he" ends inside "she" and "hers", so it is only found through failure links
+		assert_eq!(
+			find(&["he", "she", "his", "hers"], "ushers"),
+			[(0, 2, 4), (1, 1, 4), (3, 2, 6)]
+		);
+	}
+
+	#[test]
+	fn test_restart_after_mismatch() {
+		assert_eq!(find(&["seven"], "sseveseven"), [(0, 5, 10)]);
+		assert_eq!(find(&["aab"], "aaab"), [(0, 1, 4)]);
+	}
+
+	#[test]
+	fn test_multibyte() {
+		assert_eq!(find(&["fünf", "neun"], "fünfneun"), [(0, 0, 5), (1, 5, 9)]);
+	}
+
+	#[test]
+	fn test_leftmost_longest() {
+		let automaton = AhoCorasick::new(["seven", "seventeen", "teen", "en"]);
+		assert_eq!(
+			automaton.find_leftmost_longest("xseventeen".bytes()),
+			Some(Match {
+				pattern: 1,
+				start: 1,
+				end: 10
+			})
+		);
+		assert_eq!(automaton.find_leftmost_longest("xyz".bytes()), None);
+	}
+
+	#[test]
+	fn test_no_patterns() {
+		assert_eq!(find(&[], "anything"), []);
+	}
+
+	#[test]
+	fn test_brute_force() {
+		let patterns = ["a", "ab", "bab", "bc", "bca", "c", "caa"];
+		let haystack = "abccabbcaabcab";
+		let mut brute_force = patterns
+			.iter()
+			.enumerate()
+			.flat_map(|(pattern, needle)| {
+				(0..=haystack.len() - needle.len())
+					.filter(move |&start| haystack[start..].starts_with(needle))
+					.map(move |start| (pattern, start, start + needle.len()))
+			})
+			.collect::<Vec<_>>();
+		brute_force.sort_unstable();
+		assert_eq!(find(&patterns, haystack), brute_force);
+	}
+}
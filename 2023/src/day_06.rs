@@ -1,77 +1,331 @@
-const INPUT: &'static str = include_str!("../../inputs/2023/day_06.txt");
-
-pub fn main(part_two: bool) -> anyhow::Result<u64> {
-	let mut lines = INPUT.lines();
+use std::ops::RangeInclusive;
 
-	let line = lines.next().unwrap();
-	let times = line[5..]
-		.split_whitespace()
-		.map(|s| s.parse::<u64>().unwrap());
+use anyhow::anyhow;
+use num_bigint::BigUint;
 
-	let line = lines.next().unwrap();
-	let distances = line[9..]
-		.split_whitespace()
-		.map(|s| s.parse::<u64>().unwrap());
+const INPUT: &'static str = include_str!("../../inputs/2023/day_06.txt");
 
-	let records = times.zip(distances);
+pub fn main(part_two: bool) -> anyhow::Result<BigUint> {
+	let sheet = Sheet::parse(INPUT)?;
+	let boat = Boat::prompt()?;
+	if crate::option("Print race reports? (false)")?.unwrap_or(false) {
+		print_reports(&sheet, part_two, &boat)?;
+	}
 
 	match part_two {
-		false => Ok(solve_part_1(records)),
-		true => solve_part_2(records),
+		false => Ok(solve_part_1(sheet.split()?, &boat).into()),
+		true => solve_part_2(sheet.joined()?, &boat),
 	}
 }
 
-fn solve_part_1(records: impl IntoIterator<Item = (u64, u64)>) -> u64 {
+fn solve_part_1(records: impl IntoIterator<Item = (u64, u64)>, boat: &Boat) -> u64 {
 	let records = records.into_iter();
 	let result = records
-		.map(|(time, distance)| calculate_possiblities(time, distance))
+		.map(|(time, distance)| boat.count_winning_hold_times(time, distance))
 		.product::<u64>();
 
 	result
 }
 
-fn solve_part_2(records: impl IntoIterator<Item = (u64, u64)>) -> anyhow::Result<u64> {
-	let (time, distance) = records.into_iter().fold(
-		("".to_string(), "".to_string()),
-		|(time_acc, distance_acc), (time, distance)| {
-			(
-				time_acc + &time.to_string(),
-				distance_acc + &distance.to_string(),
-			)
-		},
-	);
-
-	Ok(calculate_possiblities(
-		time.parse::<u64>()?,
-		distance.parse::<u64>()?,
-	))
+fn solve_part_2((time, distance): (BigUint, BigUint), boat: &Boat) -> anyhow::Result<BigUint> {
+	match (u64::try_from(&time), u64::try_from(&distance)) {
+		(Ok(time), Ok(distance)) => Ok(boat.count_winning_hold_times(time, distance).into()),
+		_ if *boat == Boat::default() => Ok(count_winning_hold_times_big(&time, &distance)),
+		_ => Err(anyhow!(
+			"races beyond {} ms or mm are only supported with the default boat",
+			u64::MAX
+		)),
+	}
+}
+
+/// Prints how the boat fares in every race of the sheet, read the same way as the part does
+fn print_reports(sheet: &Sheet, part_two: bool, boat: &Boat) -> anyhow::Result<()> {
+	if !part_two {
+		for (time, distance) in sheet.split()? {
+			println!("{}", boat.report(time, distance));
+		}
+		return Ok(());
+	}
+	let (time, distance) = sheet.joined()?;
+	match (u64::try_from(&time), u64::try_from(&distance)) {
+		(Ok(time), Ok(distance)) => println!("{}", boat.report(time, distance)),
+		_ => println!("The race of {} ms is too long to report on", time),
+	}
+	Ok(())
+}
+
+/// The `Time:` and `Distance:` lines of the sheet, before deciding how to read them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Sheet<'a> {
+	times: &'a str,
+	distances: &'a str,
+}
+
+impl<'a> Sheet<'a> {
+	fn parse(input: &'a str) -> anyhow::Result<Self> {
+		let mut lines = input.lines();
+		let mut field = |name: &str| {
+			lines
+				.next()
+				.and_then(|line| line.strip_prefix(name))
+				.and_then(|line| line.strip_prefix(':'))
+				.ok_or(anyhow!("expected a line starting with \"{name}:\""))
+		};
+
+		Ok(Sheet {
+			times: field("Time")?,
+			distances: field("Distance")?,
+		})
+	}
+
+	/// Reads every column as a separate race.
+	fn split(&self) -> anyhow::Result<Vec<(u64, u64)>> {
+		let parse = |values: &str| -> anyhow::Result<Vec<u64>> {
+			values
+				.split_whitespace()
+				.map(|value| {
+					value
+						.parse()
+						.map_err(|err| anyhow!("invalid number \"{value}\": {err}"))
+				})
+				.collect()
+		};
+
+		let (times, distances) = (parse(self.times)?, parse(self.distances)?);
+		if times.len() != distances.len() {
+			return Err(anyhow!(
+				"{} times but {} distances",
+				times.len(),
+				distances.len()
+			));
+		}
+		Ok(times.into_iter().zip(distances).collect())
+	}
+
+	/// Reads each line as a single number, ignoring the bad kerning between the digits.
+	fn joined(&self) -> anyhow::Result<(BigUint, BigUint)> {
+		let parse = |values: &str| -> anyhow::Result<BigUint> {
+			let digits = values
+				.chars()
+				.filter(|c| !c.is_whitespace())
+				.collect::<String>();
+			BigUint::parse_bytes(digits.as_bytes(), 10)
+				.ok_or(anyhow!("invalid number \"{digits}\""))
+		};
+
+		Ok((parse(self.times)?, parse(self.distances)?))
+	}
+}
+
+/// Counts the hold times that beat `distance_to_beat` with the default boat, on numbers of any size.
+fn count_winning_hold_times_big(time: &BigUint, distance_to_beat: &BigUint) -> BigUint {
+	let beats_record = |hold_time: &BigUint| hold_time * (time - hold_time) > *distance_to_beat;
+
+	let squared = time * time;
+	let quadrupled = distance_to_beat * 4u32;
+	if squared < quadrupled {
+		return BigUint::ZERO;
+	}
+	let half = time / 2u32;
+	let mut min_hold_time = (time - (squared - quadrupled).sqrt()) / 2u32;
+
+	// the square root rounds down, so the estimate may be off by one in either direction
+	while min_hold_time > BigUint::ZERO && beats_record(&(&min_hold_time - 1u32)) {
+		min_hold_time -= 1u32;
+	}
+	while min_hold_time <= half && !beats_record(&min_hold_time) {
+		min_hold_time += 1u32;
+	}
+	if min_hold_time > half {
+		return BigUint::ZERO;
+	}
+
+	// the winning hold times are symmetric around `time / 2`
+	time - &min_hold_time * 2u32 + 1u32
+}
+
+/// How a boat picks up speed while its button is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Boat {
+	/// Speed gained per millisecond of holding the button
+	acceleration: u64,
+	/// Speed the boat cannot exceed, no matter how long the button is held
+	max_speed: Option<u64>,
+	/// Whether matching the record counts as a win
+	ties_win: bool,
+}
+
+impl Default for Boat {
+	fn default() -> Self {
+		Boat {
+			acceleration: 1,
+			max_speed: None,
+			ties_win: false,
+		}
+	}
+}
+
+impl Boat {
+	fn prompt() -> anyhow::Result<Self> {
+		let default = Boat::default();
+		Ok(Boat {
+			acceleration: crate::option("Acceleration (1)")?.unwrap_or(default.acceleration),
+			max_speed: crate::option("Maximum speed (none)")?.or(default.max_speed),
+			ties_win: crate::option("Do ties win? (false)")?.unwrap_or(default.ties_win),
+		})
+	}
+
+	fn speed(&self, hold_time: u64) -> u128 {
+		let speed = u128::from(self.acceleration) * u128::from(hold_time);
+		match self.max_speed {
+			Some(max_speed) => speed.min(u128::from(max_speed)),
+			None => speed,
+		}
+	}
+
+	/// Distance travelled in a race of `time` milliseconds. Saturates at `u128::MAX`.
+	fn distance(&self, time: u64, hold_time: u64) -> u128 {
+		self.speed(hold_time)
+			.saturating_mul(u128::from(time.saturating_sub(hold_time)))
+	}
+
+	/// Returns the range of hold times that win against the record `distance_to_beat`, or `None`
+	/// if the record cannot be beaten.
+	///
+	/// Until the speed cap is hit the distance is quadratic in the hold time, afterwards it
+	/// falls linearly. The distance rises and then falls, so the winning hold times of both
+	/// phases join up into a single range.
+	fn winning_hold_times(&self, time: u64, distance_to_beat: u64) -> Option<RangeInclusive<u64>> {
+		let needed = u128::from(distance_to_beat) + u128::from(!self.ties_win);
+
+		// the last hold time at which the boat is still below the speed cap
+		let last_accelerating = match (self.max_speed, self.acceleration) {
+			(Some(max_speed), acceleration) if acceleration > 0 => {
+				(max_speed / acceleration).min(time)
+			}
+			_ => time,
+		};
+
+		let accelerating = match u128::from(self.acceleration) {
+			0 => (needed == 0).then_some(0..=time),
+			// `h * (time - h) * acceleration >= needed` iff `h * (time - h) > ceil(needed / acceleration) - 1`
+			acceleration => match needed.div_ceil(acceleration).checked_sub(1) {
+				Some(to_beat) => winning_hold_times(time, to_beat),
+				None => Some(0..=time),
+			},
+		}
+		.filter(|range| *range.start() <= last_accelerating)
+		.map(|range| *range.start()..=*range.end().min(&last_accelerating));
+
+		let capped = match self.max_speed.filter(|_| last_accelerating < time) {
+			Some(max_speed) => {
+				let latest = match u128::from(max_speed) {
+					0 => (needed == 0).then_some(time),
+					// `max_speed * (time - h) >= needed` iff `h <= time - ceil(needed / max_speed)`
+					max_speed => u128::from(time)
+						.checked_sub(needed.div_ceil(max_speed))
+						.map(|latest| latest as u64),
+				};
+				latest
+					.filter(|&latest| latest > last_accelerating)
+					.map(|latest| last_accelerating + 1..=latest)
+			}
+			None => None,
+		};
+
+		match (accelerating, capped) {
+			(Some(accelerating), Some(capped)) => Some(*accelerating.start()..=*capped.end()),
+			(accelerating, capped) => accelerating.or(capped),
+		}
+	}
+
+	fn count_winning_hold_times(&self, time: u64, distance_to_beat: u64) -> u64 {
+		self.winning_hold_times(time, distance_to_beat)
+			.map_or(0, |range| range.end() - range.start() + 1)
+	}
+
+	/// Returns the shortest hold time that travels the furthest.
+	///
+	/// Without a speed cap the best hold time is half the race. With one, it is either that or
+	/// right around the moment the cap is reached, whichever comes first.
+	fn optimal_hold_time(&self, time: u64) -> u64 {
+		let mut candidates = vec![time / 2, time.div_ceil(2)];
+		if self.acceleration == 0 {
+			// a boat that never moves travels nowhere, so waiting does not help
+			candidates.push(0);
+		}
+		if let Some(max_speed) = self.max_speed.filter(|_| self.acceleration > 0) {
+			let reaches_cap = max_speed / self.acceleration;
+			candidates.extend([reaches_cap, reaches_cap.saturating_add(1)]);
+		}
+
+		candidates
+			.into_iter()
+			.filter(|&hold_time| hold_time <= time)
+			.min_by_key(|&hold_time| (std::cmp::Reverse(self.distance(time, hold_time)), hold_time))
+			.unwrap()
+	}
+
+	fn report(&self, time: u64, distance_to_beat: u64) -> String {
+		let optimal_hold_time = self.optimal_hold_time(time);
+		let winning = match self.winning_hold_times(time, distance_to_beat) {
+			Some(range) => format!("win holding {}..={}", range.start(), range.end()),
+			None => "cannot win".to_string(),
+		};
+		format!(
+			"{time} ms, record {distance_to_beat} mm: {winning}, best hold {optimal_hold_time} ms travels {} mm",
+			self.distance(time, optimal_hold_time)
+		)
+	}
 }
 
-fn calculate_possiblities(time: u64, distance_to_beat: u64) -> u64 {
-	let time = time as f64;
-	let distance_to_beat = distance_to_beat as f64;
+/// Returns the range of hold times that travel further than `distance_to_beat`, or `None` if the
+/// record cannot be beaten.
+///
+/// Holding for `h` travels `h * (time - h)`, so the winning hold times lie strictly between the
+/// roots of `h^2 - time * h + distance_to_beat`. Everything is computed on integers, so that the
+/// bounds stay exact for large races and roots that are integers themselves.
+fn winning_hold_times(time: u64, distance_to_beat: u128) -> Option<RangeInclusive<u64>> {
+	let time = u128::from(time);
+	let beats_record = |hold_time: u128| hold_time * (time - hold_time) > distance_to_beat;
 
-	let constant_part = time / 2.0;
-	let linear_part = (time.powi(2) / 4.0 - distance_to_beat).sqrt();
+	let discriminant = (time * time).checked_sub(4 * distance_to_beat)?;
+	let mut min_hold_time = (time - discriminant.isqrt()) / 2;
 
-	let min_charge_time = constant_part - linear_part;
-	let max_charge_time = constant_part + linear_part;
+	// the integer square root rounds down, so the estimate may be off by one in either direction
+	while min_hold_time > 0 && beats_record(min_hold_time - 1) {
+		min_hold_time -= 1;
+	}
+	while min_hold_time <= time / 2 && !beats_record(min_hold_time) {
+		min_hold_time += 1;
+	}
+	if min_hold_time > time / 2 {
+		return None;
+	}
 
-	max_charge_time.ceil() as u64 - min_charge_time.floor() as u64 - 1
+	// the distance is symmetric around `time / 2`
+	let max_hold_time = time - min_hold_time;
+	Some(min_hold_time as u64..=max_hold_time as u64)
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	const EXAMPLE: &str = "Time:      7  15   30\nDistance:  9  40  200";
+
 	const EXAMPLE_RECORDS: [(u64, u64); 3] = [(7, 9), (15, 40), (30, 200)];
 
+	fn calculate_possiblities(time: u64, distance_to_beat: u64) -> u64 {
+		Boat::default().count_winning_hold_times(time, distance_to_beat)
+	}
+
 	mod part_1 {
 		use super::*;
 
 		#[test]
 		fn test_example() {
-			assert_eq!(solve_part_1(EXAMPLE_RECORDS), 288);
+			assert_eq!(solve_part_1(EXAMPLE_RECORDS, &Boat::default()), 288);
 		}
 	}
 
@@ -80,10 +334,87 @@ mod tests {
 
 		#[test]
 		fn test_example_part_2() {
-			assert_eq!(solve_part_2(EXAMPLE_RECORDS).unwrap(), 71503);
+			let races = Sheet::parse(EXAMPLE).unwrap().joined().unwrap();
+			assert_eq!(
+				solve_part_2(races, &Boat::default()).unwrap(),
+				BigUint::from(71503u32)
+			);
+		}
+
+		#[test]
+		fn test_beyond_u64() {
+			let time = BigUint::from(u64::MAX) * 4u32;
+			let races = (time.clone(), BigUint::ZERO);
+			assert_eq!(
+				solve_part_2(races.clone(), &Boat::default()).unwrap(),
+				time - 1u32
+			);
+
+			let boat = Boat {
+				ties_win: true,
+				..Boat::default()
+			};
+			assert!(solve_part_2(races, &boat).is_err());
 		}
 	}
 
+	#[test]
+	fn test_sheet_split() {
+		let sheet = Sheet::parse(EXAMPLE).unwrap();
+		assert_eq!(sheet.split().unwrap(), EXAMPLE_RECORDS);
+	}
+
+	#[test]
+	fn test_sheet_joined() {
+		let sheet = Sheet::parse(EXAMPLE).unwrap();
+		assert_eq!(
+			sheet.joined().unwrap(),
+			(BigUint::from(71530u32), BigUint::from(940200u32))
+		);
+
+		// leading zeros of later columns are digits in the middle of the joined number
+		let sheet = Sheet::parse("Time: 1 05\nDistance: 0 0").unwrap();
+		assert_eq!(sheet.split().unwrap(), [(1, 0), (5, 0)]);
+		assert_eq!(
+			sheet.joined().unwrap(),
+			(BigUint::from(105u32), BigUint::ZERO)
+		);
+
+		let sheet = Sheet::parse("Time: 123456789 123456789 123456789\nDistance: 1").unwrap();
+		assert_eq!(
+			sheet.joined().unwrap().0.to_string(),
+			"123456789123456789123456789"
+		);
+	}
+
+	#[test]
+	fn test_sheet_errors() {
+		assert!(Sheet::parse("Distance: 9\nTime: 7").is_err());
+		assert!(Sheet::parse("Time: 7").is_err());
+		assert!(Sheet::parse("Time: 7 15\nDistance: 9")
+			.unwrap()
+			.split()
+			.is_err());
+		assert!(Sheet::parse("Time: 7 x\nDistance: 9")
+			.unwrap()
+			.joined()
+			.is_err());
+	}
+
+	#[test]
+	fn test_count_winning_hold_times_big() {
+		for (time, distance) in EXAMPLE_RECORDS {
+			assert_eq!(
+				count_winning_hold_times_big(&time.into(), &distance.into()),
+				BigUint::from(calculate_possiblities(time, distance))
+			);
+		}
+		assert_eq!(
+			count_winning_hold_times_big(&4u32.into(), &4u32.into()),
+			BigUint::ZERO
+		);
+	}
+
 	#[test]
 	fn test_calculate_possibilities_1() {
 		assert_eq!(calculate_possiblities(7, 9), 4);
@@ -103,4 +434,143 @@ mod tests {
 	fn test_calculate_possibilities_4() {
 		assert_eq!(calculate_possiblities(71530, 940200), 71503);
 	}
+
+	#[test]
+	fn test_winning_hold_times() {
+		assert_eq!(winning_hold_times(7, 9), Some(2..=5));
+		// holding for 10 or 20 travels exactly 200, which does not beat the record
+		assert_eq!(winning_hold_times(30, 200), Some(11..=19));
+		assert_eq!(winning_hold_times(4, 4), None);
+		assert_eq!(winning_hold_times(0, 0), None);
+		assert_eq!(winning_hold_times(2, 0), Some(1..=1));
+	}
+
+	#[test]
+	fn test_winning_hold_times_large() {
+		// `time^2` exceeds 2^53, where `f64` can no longer represent every integer
+		let time = 3_000_000_001;
+		let min_hold_time = 1_000_000_000;
+		let distance_to_beat = min_hold_time * (time - min_hold_time);
+		assert_eq!(
+			winning_hold_times(time, distance_to_beat.into()),
+			Some(min_hold_time + 1..=time - min_hold_time - 1)
+		);
+		assert_eq!(winning_hold_times(u64::MAX, 0), Some(1..=u64::MAX - 1));
+	}
+
+	#[test]
+	fn test_boat_default_matches_puzzle() {
+		let boat = Boat::default();
+		assert_eq!(boat.winning_hold_times(30, 200), Some(11..=19));
+		assert_eq!(boat.optimal_hold_time(7), 3);
+		assert_eq!(boat.distance(7, 3), 12);
+	}
+
+	#[test]
+	fn test_boat_ties_win() {
+		let boat = Boat {
+			ties_win: true,
+			..Boat::default()
+		};
+		assert_eq!(boat.winning_hold_times(30, 200), Some(10..=20));
+		assert_eq!(boat.winning_hold_times(4, 4), Some(2..=2));
+		assert_eq!(boat.winning_hold_times(0, 0), Some(0..=0));
+	}
+
+	#[test]
+	fn test_boat_acceleration() {
+		let boat = Boat {
+			acceleration: 3,
+			..Boat::default()
+		};
+		// holding for 2 travels 2 * 3 * 5 = 30
+		assert_eq!(boat.winning_hold_times(7, 29), Some(2..=5));
+		assert_eq!(boat.winning_hold_times(7, 30), Some(3..=4));
+		assert_eq!(boat.optimal_hold_time(7), 3);
+	}
+
+	#[test]
+	fn test_boat_max_speed() {
+		let boat = Boat {
+			max_speed: Some(2),
+			..Boat::default()
+		};
+		// the speed is capped at 2 from a hold time of 2 on, so holding longer only loses time
+		assert_eq!(boat.optimal_hold_time(30), 2);
+		assert_eq!(boat.distance(30, 2), 56);
+		assert_eq!(boat.winning_hold_times(30, 40), Some(2..=9));
+		assert_eq!(boat.winning_hold_times(30, 56), None);
+	}
+
+	#[test]
+	fn test_boat_report() {
+		assert_eq!(
+			Boat::default().report(7, 9),
+			"7 ms, record 9 mm: win holding 2..=5, best hold 3 ms travels 12 mm"
+		);
+	}
+
+	fn boat_brute_force(
+		boat: &Boat,
+		time: u64,
+		distance_to_beat: u64,
+	) -> Option<RangeInclusive<u64>> {
+		let wins = |distance: u128| match boat.ties_win {
+			true => distance >= u128::from(distance_to_beat),
+			false => distance > u128::from(distance_to_beat),
+		};
+		let mut winning = (0..=time).filter(|&h| wins(boat.distance(time, h)));
+		let min = winning.next()?;
+		let max = winning.next_back().unwrap_or(min);
+		Some(min..=max)
+	}
+
+	fn winning_hold_times_brute_force(
+		time: u64,
+		distance_to_beat: u64,
+	) -> Option<RangeInclusive<u64>> {
+		let mut winning = (0..=time).filter(|h| h * (time - h) > distance_to_beat);
+		let min = winning.next()?;
+		let max = winning.next_back().unwrap_or(min);
+		Some(min..=max)
+	}
+
+	proptest::proptest! {
+		#[test]
+		fn test_winning_hold_times_brute_force(time in 0u64..2000, distance_to_beat in 0u64..1_000_000) {
+			proptest::prop_assert_eq!(
+				winning_hold_times(time, distance_to_beat.into()),
+				winning_hold_times_brute_force(time, distance_to_beat)
+			);
+		}
+
+		#[test]
+		fn test_boat_brute_force(
+			acceleration in 0u64..5,
+			max_speed in proptest::option::of(0u64..40),
+			ties_win: bool,
+			time in 0u64..200,
+			distance_to_beat in 0u64..2000,
+		) {
+			let boat = Boat { acceleration, max_speed, ties_win };
+			proptest::prop_assert_eq!(
+				boat.winning_hold_times(time, distance_to_beat),
+				boat_brute_force(&boat, time, distance_to_beat)
+			);
+
+			let best = (0..=time).max_by_key(|&h| (boat.distance(time, h), std::cmp::Reverse(h))).unwrap();
+			proptest::prop_assert_eq!(boat.optimal_hold_time(time), best);
+		}
+
+		#[test]
+		fn test_winning_hold_times_exact_roots(time in 0u64..2000, hold_time in 0u64..2000) {
+			// records that are reached exactly by some hold time put the roots on integers
+			let hold_time = hold_time.min(time);
+			let distance_to_beat = hold_time * (time - hold_time);
+			proptest::prop_assert_eq!(
+				winning_hold_times(time, distance_to_beat.into()),
+				winning_hold_times_brute_force(time, distance_to_beat)
+			);
+		}
+	}
 }
@@ -1,21 +1,34 @@
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+
+use probability::HandTypeTable;
+
+mod probability;
 
 const INPUT: &'static str = include_str!("../../inputs/2023/day_07.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u64> {
-	solve(INPUT.lines(), part_two)
+	let mut ruleset = match part_two {
+		false => Ruleset::standard(),
+		true => Ruleset::jokers(),
+	};
+	if crate::option::<bool>("Count straights? (false)")?.unwrap_or(false) {
+		ruleset = ruleset.with_straights();
+	}
+	if crate::option::<bool>("Explain every hand? (false)")?.unwrap_or(false) {
+		for line in explain(INPUT.lines(), &ruleset)? {
+			println!("{}", line);
+		}
+	}
+	if crate::option::<bool>("Print hand type probabilities? (false)")?.unwrap_or(false) {
+		for line in probabilities(INPUT.lines(), &ruleset)? {
+			println!("{}", line);
+		}
+	}
+	solve(INPUT.lines(), &ruleset)
 }
 
-fn solve(
-	lines: impl IntoIterator<Item = &'static str>,
-	replace_jack_with_joker: bool,
-) -> anyhow::Result<u64> {
-	let mut hands = lines
-		.into_iter()
-		.map(|s| parse_hand(&s, replace_jack_with_joker))
-		.collect::<Result<Vec<_>, _>>()?;
-	hands.sort_unstable();
+fn solve(lines: impl IntoIterator<Item = &'static str>, ruleset: &Ruleset) -> anyhow::Result<u64> {
+	let hands = rank_hands(lines, ruleset)?;
 	let result = hands
 		.iter()
 		.enumerate()
@@ -24,29 +37,270 @@ fn solve(
 	Ok(result)
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Hand {
-	cards: [Card; 5],
-	bid: u64,
+/// Parses the hands and sorts them from weakest to strongest
+fn rank_hands(
+	lines: impl IntoIterator<Item = &'static str>,
+	ruleset: &Ruleset,
+) -> anyhow::Result<Vec<Hand>> {
+	let mut hands = lines
+		.into_iter()
+		.map(|s| parse_hand(s, ruleset))
+		.collect::<Result<Vec<_>, _>>()?;
+	hands.sort_unstable();
+	Ok(hands)
+}
+
+/// Lists how likely every hand type is for a random hand and what the bids are expected to win
+fn probabilities(
+	lines: impl IntoIterator<Item = &'static str>,
+	ruleset: &Ruleset,
+) -> anyhow::Result<Vec<String>> {
+	let table = HandTypeTable::enumerate(ruleset)?;
+	let mut report = table
+		.counts
+		.iter()
+		.map(|&(hand_type, count)| {
+			format!(
+				"{:?}: {} / {} = {:.6}",
+				hand_type,
+				count,
+				table.total,
+				table.probability(hand_type)
+			)
+		})
+		.collect::<Vec<_>>();
+
+	let hands = rank_hands(lines, ruleset)?;
+	let hands = hands
+		.iter()
+		.map(|hand| (hand.hand_type, hand.bid))
+		.collect::<Vec<_>>();
+	report.push(format!(
+		"Expected winnings against random hands: {:.1}",
+		table.expected_winnings(&hands)
+	));
+	Ok(report)
+}
+
+/// Describes every hand, weakest first: its type, what its wild cards became, its rank and what
+/// it wins
+fn explain(
+	lines: impl IntoIterator<Item = &'static str>,
+	ruleset: &Ruleset,
+) -> anyhow::Result<Vec<String>> {
+	let hands = rank_hands(lines, ruleset)?;
+	let lines = hands.iter().enumerate().map(|(i, hand)| {
+		let rank = i as u64 + 1;
+		let labels = hand.cards.iter().map(|card| card.label).collect::<String>();
+		let wilds = match hand.wilds_as.is_empty() {
+			true => String::new(),
+			false => format!(" (wild cards as {})", String::from_iter(&hand.wilds_as)),
+		};
+		format!(
+			"{} {:?}{}: rank {}, wins {} * {} = {}",
+			labels,
+			hand.hand_type,
+			wilds,
+			rank,
+			rank,
+			hand.bid,
+			rank * hand.bid
+		)
+	});
+	Ok(lines.collect())
+}
+
+/// Amount of consecutive cards that make a straight
+const STRAIGHT_LENGTH: usize = 5;
+
+/// Configures how hands are read and ranked
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Ruleset {
+	/// Card labels from weakest to strongest
+	order: Vec<char>,
+	/// Card labels that act like whatever card makes the strongest hand type
+	wild: Vec<char>,
+	hand_size: usize,
+	/// Hand types from weakest to strongest. Hand types missing here never count.
+	hand_types: Vec<HandType>,
 }
 
-impl Hand {
-	fn joker_count(&self) -> u64 {
-		self.cards.iter().filter(|&&c| c == Card::Joker).count() as u64
+impl Ruleset {
+	fn standard() -> Self {
+		Ruleset {
+			order: "23456789TJQKA".chars().collect(),
+			wild: Vec::new(),
+			hand_size: 5,
+			hand_types: HandType::CAMEL_CARDS.to_vec(),
+		}
+	}
+
+	/// Jacks are jokers, which are wild but the weakest card on their own
+	fn jokers() -> Self {
+		Ruleset {
+			order: "J23456789TQKA".chars().collect(),
+			wild: vec!['J'],
+			..Ruleset::standard()
+		}
 	}
 
-	fn hand_type(&self) -> HandType {
-		let without_jokers = self.cards.into_iter().filter(|&c| c != Card::Joker);
-		let uniques = HashSet::<Card>::from_iter(without_jokers.clone()).len();
-		match uniques {
-			0 | 1 => HandType::FiveOfAKind, // this is 0 when there are 5 jokers
-			2 => four_pair_or_full_house(without_jokers, self.joker_count()),
-			3 => three_pair_or_two_pair(without_jokers, self.joker_count()),
-			4 => HandType::OnePair,
-			5 => HandType::HighCard,
-			_ => panic!("invalid hand"),
+	/// Ranks straights between three of a kind and a full house, like poker does
+	fn with_straights(self) -> Self {
+		Ruleset {
+			hand_types: HandType::WITH_STRAIGHTS.to_vec(),
+			..self
 		}
 	}
+
+	fn card(&self, label: char) -> anyhow::Result<Card> {
+		let strength = self
+			.order
+			.iter()
+			.position(|&c| c == label)
+			.ok_or(anyhow::anyhow!("invalid card '{}'", label))?;
+		Ok(Card {
+			strength,
+			label,
+			wild: self.wild.contains(&label),
+		})
+	}
+
+	/// Returns the strongest hand type in this ruleset that the cards qualify for
+	fn classify(&self, cards: &[Card]) -> Option<HandType> {
+		let wilds = cards.iter().filter(|card| card.wild).count();
+		let groups = groups(cards);
+		let strengths = groups
+			.iter()
+			.map(|&(_, strength)| strength)
+			.collect::<Vec<_>>();
+
+		let shape = HandShape {
+			largest: groups.first().map_or(0, |&(size, _)| size),
+			second_largest: groups.get(1).map_or(0, |&(size, _)| size),
+			wilds,
+			straight: self.hand_types.contains(&HandType::Straight)
+				&& self.has_straight(&strengths, wilds),
+		};
+
+		self.hand_types
+			.iter()
+			.rev()
+			.find(|hand_type| shape.qualifies_for(hand_type))
+			.copied()
+	}
+
+	/// Whether the cards contain a straight, where wild cards may fill the gaps. Wild cards are
+	/// not part of the order of a straight themselves.
+	fn has_straight(&self, distinct_strengths: &[usize], wilds: usize) -> bool {
+		self.straight_window(distinct_strengths, wilds).is_some()
+	}
+
+	/// The strongest consecutive natural cards that make a straight together with the wild cards
+	fn straight_window(&self, distinct_strengths: &[usize], wilds: usize) -> Option<Vec<usize>> {
+		let natural = self.natural_strengths();
+		let window = natural.windows(STRAIGHT_LENGTH).rev().find(|window| {
+			let present = window
+				.iter()
+				.filter(|strength| distinct_strengths.contains(strength))
+				.count();
+			present + wilds >= STRAIGHT_LENGTH
+		})?;
+		Some(window.to_vec())
+	}
+
+	/// Strengths of all cards that are not wild, from weakest to strongest
+	fn natural_strengths(&self) -> Vec<usize> {
+		(0..self.order.len())
+			.filter(|&strength| !self.wild.contains(&self.order[strength]))
+			.collect()
+	}
+
+	/// Labels of the cards that the wild cards act as to make `hand_type`, in the order the wild
+	/// cards appear in
+	fn resolve_wilds(&self, cards: &[Card], hand_type: HandType) -> Vec<char> {
+		let wilds = cards.iter().filter(|card| card.wild).count();
+		if wilds == 0 {
+			return Vec::new();
+		}
+
+		let groups = groups(cards);
+		let distinct_strengths = groups
+			.iter()
+			.map(|&(_, strength)| strength)
+			.collect::<Vec<_>>();
+
+		let mut resolved = Vec::with_capacity(wilds);
+		if hand_type == HandType::Straight {
+			// this cannot panic because the cards qualified for a straight
+			let window = self.straight_window(&distinct_strengths, wilds).unwrap();
+			let missing = window
+				.iter()
+				.filter(|strength| !distinct_strengths.contains(strength));
+			resolved.extend(missing);
+		} else {
+			// fill up the groups with wild cards, strongest natural cards first if there are none
+			let mut targets = groups.iter().copied().chain(
+				self.natural_strengths()
+					.into_iter()
+					.rev()
+					.filter(|strength| !distinct_strengths.contains(strength))
+					.map(|strength| (0, strength)),
+			);
+			for needed in hand_type.group_sizes() {
+				let Some((size, strength)) = targets.next() else {
+					break;
+				};
+				let missing = needed.saturating_sub(size).min(wilds - resolved.len());
+				resolved.extend(std::iter::repeat_n(strength, missing));
+			}
+		}
+
+		// remaining wild cards do not matter, so they join the strongest resolved or largest group
+		let fallback = resolved
+			.iter()
+			.max()
+			.copied()
+			.or(groups.first().map(|&(_, strength)| strength))
+			.or(self.natural_strengths().last().copied());
+		if let Some(fallback) = fallback {
+			resolved.resize(wilds, fallback);
+		}
+
+		resolved
+			.into_iter()
+			.map(|strength| self.order[strength])
+			.collect()
+	}
+
+	/// Packs the hand type and card strengths into a single integer that sorts the same way as
+	/// the hands
+	fn sort_key(&self, hand_type: HandType, cards: &[Card]) -> anyhow::Result<u64> {
+		let base = self.order.len() as u64;
+		let type_rank = self.type_rank(&hand_type).ok_or(anyhow::anyhow!(
+			"{:?} is not part of the ruleset",
+			hand_type
+		))?;
+		cards
+			.iter()
+			.try_fold(type_rank as u64, |key, card| {
+				key.checked_mul(base)?.checked_add(card.strength as u64)
+			})
+			.ok_or(anyhow::anyhow!("hand is too large to fit into a sort key"))
+	}
+
+	fn type_rank(&self, hand_type: &HandType) -> Option<usize> {
+		self.hand_types.iter().position(|t| t == hand_type)
+	}
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Hand {
+	cards: Vec<Card>,
+	bid: u64,
+	hand_type: HandType,
+	/// What the wild cards act as, in the order they appear in
+	wilds_as: Vec<char>,
+	sort_key: u64,
 }
 
 impl PartialOrd for Hand {
@@ -57,129 +311,146 @@ impl PartialOrd for Hand {
 
 impl Ord for Hand {
 	fn cmp(&self, other: &Self) -> Ordering {
-		if self.hand_type().cmp(&other.hand_type()) != Ordering::Equal {
-			return self.hand_type().cmp(&other.hand_type());
-		}
-		for i in 0..5 {
-			match self.cards[i].cmp(&other.cards[i]) {
-				Ordering::Equal => continue,
-				ordering => return ordering,
-			}
-		}
-		Ordering::Equal
+		self.sort_key.cmp(&other.sort_key)
 	}
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
-enum Card {
-	Joker,
-	Two,
-	Three,
-	Four,
-	Five,
-	Six,
-	Seven,
-	Eight,
-	Nine,
-	Ten,
-	Jack,
-	Queen,
-	King,
-	Ace,
-}
-
-impl Card {
-	fn try_from_char(value: char, replace_jack_with_joker: bool) -> anyhow::Result<Self> {
-		match (value, replace_jack_with_joker) {
-			('2', _) => Ok(Card::Two),
-			('3', _) => Ok(Card::Three),
-			('4', _) => Ok(Card::Four),
-			('5', _) => Ok(Card::Five),
-			('6', _) => Ok(Card::Six),
-			('7', _) => Ok(Card::Seven),
-			('8', _) => Ok(Card::Eight),
-			('9', _) => Ok(Card::Nine),
-			('T', _) => Ok(Card::Ten),
-			('J', false) => Ok(Card::Jack),
-			('J', true) => Ok(Card::Joker),
-			('Q', _) => Ok(Card::Queen),
-			('K', _) => Ok(Card::King),
-			('A', _) => Ok(Card::Ace),
-			_ => Err(anyhow::anyhow!("invalid card '{}'", value)),
-		}
-	}
+struct Card {
+	strength: usize,
+	label: char,
+	wild: bool,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 enum HandType {
 	HighCard,
 	OnePair,
 	TwoPair,
 	ThreeOfAKind,
+	Straight,
 	FullHouse,
 	FourOfAKind,
 	FiveOfAKind,
 }
 
-fn parse_hand(input: &str, replace_jack_with_joker: bool) -> anyhow::Result<Hand> {
+impl HandType {
+	/// Sizes of the groups of equal cards the hand type needs, largest first
+	fn group_sizes(&self) -> &'static [usize] {
+		match self {
+			HandType::HighCard | HandType::Straight => &[],
+			HandType::OnePair => &[2],
+			HandType::TwoPair => &[2, 2],
+			HandType::ThreeOfAKind => &[3],
+			HandType::FullHouse => &[3, 2],
+			HandType::FourOfAKind => &[4],
+			HandType::FiveOfAKind => &[5],
+		}
+	}
+
+	/// Camel Cards knows no straights. Flushes are out of scope for every ruleset, so there is no
+	/// hand type for them.
+	const CAMEL_CARDS: [HandType; 7] = [
+		HandType::HighCard,
+		HandType::OnePair,
+		HandType::TwoPair,
+		HandType::ThreeOfAKind,
+		HandType::FullHouse,
+		HandType::FourOfAKind,
+		HandType::FiveOfAKind,
+	];
+
+	const WITH_STRAIGHTS: [HandType; 8] = [
+		HandType::HighCard,
+		HandType::OnePair,
+		HandType::TwoPair,
+		HandType::ThreeOfAKind,
+		HandType::Straight,
+		HandType::FullHouse,
+		HandType::FourOfAKind,
+		HandType::FiveOfAKind,
+	];
+}
+
+/// What a hand consists of, independent of the actual cards
+struct HandShape {
+	largest: usize,
+	second_largest: usize,
+	wilds: usize,
+	straight: bool,
+}
+
+impl HandShape {
+	fn qualifies_for(&self, hand_type: &HandType) -> bool {
+		let missing = |size: usize, group: usize| size.saturating_sub(group);
+		match hand_type {
+			HandType::HighCard => true,
+			HandType::OnePair => self.largest + self.wilds >= 2,
+			HandType::TwoPair => {
+				missing(2, self.largest) + missing(2, self.second_largest) <= self.wilds
+			}
+			HandType::ThreeOfAKind => self.largest + self.wilds >= 3,
+			HandType::Straight => self.straight,
+			HandType::FullHouse => {
+				missing(3, self.largest) + missing(2, self.second_largest) <= self.wilds
+			}
+			HandType::FourOfAKind => self.largest + self.wilds >= 4,
+			HandType::FiveOfAKind => self.largest + self.wilds >= 5,
+		}
+	}
+}
+
+/// Groups equal cards that are not wild. Returns the size and strength of each group, largest and
+/// then strongest first.
+fn groups(cards: &[Card]) -> Vec<(usize, usize)> {
+	let mut strengths = cards
+		.iter()
+		.filter(|card| !card.wild)
+		.map(|card| card.strength)
+		.collect::<Vec<_>>();
+	strengths.sort_unstable();
+
+	let mut groups = strengths
+		.chunk_by(|a, b| a == b)
+		.map(|group| (group.len(), group[0]))
+		.collect::<Vec<_>>();
+	groups.sort_unstable_by(|a, b| b.cmp(a));
+	groups
+}
+
+fn parse_hand(input: &str, ruleset: &Ruleset) -> anyhow::Result<Hand> {
 	let (cards, bid) = input.split_once(" ").ok_or(anyhow::anyhow!(
 		"expected input to contain a space delimiting the cards and bid, got {}",
 		input
 	))?;
 
+	let cards = cards
+		.chars()
+		.map(|char| ruleset.card(char))
+		.collect::<Result<Vec<_>, _>>()?;
+	if cards.len() != ruleset.hand_size {
+		return Err(anyhow::anyhow!(
+			"expected {} cards, got {}",
+			ruleset.hand_size,
+			cards.len()
+		));
+	}
+	let hand_type = ruleset.classify(&cards).ok_or(anyhow::anyhow!(
+		"hand {} does not qualify for any hand type of the ruleset",
+		input
+	))?;
+
 	let result = Hand {
-		cards: cards
-			.chars()
-			.map(|char| Card::try_from_char(char, replace_jack_with_joker))
-			.collect::<Result<Vec<_>, _>>()?
-			.try_into()
-			.or_else(|cards: Vec<_>| {
-				Err(anyhow::anyhow!("expected 5 cards, got {}", cards.len()))
-			})?,
 		bid: bid.parse::<u64>()?,
+		hand_type,
+		wilds_as: ruleset.resolve_wilds(&cards, hand_type),
+		sort_key: ruleset.sort_key(hand_type, &cards)?,
+		cards,
 	};
 
 	Ok(result)
 }
 
-fn four_pair_or_full_house(
-	without_jokers: impl Iterator<Item = Card>,
-	joker_count: u64,
-) -> HandType {
-	let mut counts = HashMap::<Card, u64>::with_capacity(2);
-	without_jokers.for_each(|card| {
-		counts
-			.entry(card)
-			.and_modify(|count| *count += 1)
-			.or_insert(1);
-	});
-	let max_count = counts.iter().map(|(_, count)| count).max().unwrap();
-	match max_count + joker_count {
-		4 => HandType::FourOfAKind,
-		3 => HandType::FullHouse,
-		_ => panic!("invalid hand"),
-	}
-}
-
-fn three_pair_or_two_pair(
-	without_jokers: impl Iterator<Item = Card>,
-	joker_count: u64,
-) -> HandType {
-	let mut counts = HashMap::<Card, u64>::with_capacity(3);
-	without_jokers.for_each(|card| {
-		counts
-			.entry(card)
-			.and_modify(|count| *count += 1)
-			.or_insert(1);
-	});
-	let max_count = counts.iter().map(|(_, count)| count).max().unwrap();
-	match max_count + joker_count {
-		3 => HandType::ThreeOfAKind,
-		2 => HandType::TwoPair,
-		_ => panic!("invalid hand"),
-	}
-}
-
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -197,7 +468,7 @@ mod tests {
 
 		#[test]
 		fn test_example() {
-			assert_eq!(solve(EXAMPLE_LINES, false).unwrap(), 6440);
+			assert_eq!(solve(EXAMPLE_LINES, &Ruleset::standard()).unwrap(), 6440);
 		}
 	}
 
@@ -206,7 +477,153 @@ mod tests {
 
 		#[test]
 		fn test_example() {
-			assert_eq!(solve(EXAMPLE_LINES, true).unwrap(), 5905);
+			assert_eq!(solve(EXAMPLE_LINES, &Ruleset::jokers()).unwrap(), 5905);
 		}
 	}
+
+	fn classify(ruleset: &Ruleset, cards: &str) -> Option<HandType> {
+		let cards = cards
+			.chars()
+			.map(|label| ruleset.card(label).unwrap())
+			.collect::<Vec<_>>();
+		ruleset.classify(&cards)
+	}
+
+	#[test]
+	fn test_classify_standard() {
+		let ruleset = Ruleset::standard();
+		assert_eq!(classify(&ruleset, "AAAAA"), Some(HandType::FiveOfAKind));
+		assert_eq!(classify(&ruleset, "AA8AA"), Some(HandType::FourOfAKind));
+		assert_eq!(classify(&ruleset, "23332"), Some(HandType::FullHouse));
+		assert_eq!(classify(&ruleset, "TTT98"), Some(HandType::ThreeOfAKind));
+		assert_eq!(classify(&ruleset, "23432"), Some(HandType::TwoPair));
+		assert_eq!(classify(&ruleset, "A23A4"), Some(HandType::OnePair));
+		assert_eq!(classify(&ruleset, "23456"), Some(HandType::HighCard));
+	}
+
+	#[test]
+	fn test_classify_jokers() {
+		let ruleset = Ruleset::jokers();
+		assert_eq!(classify(&ruleset, "JJJJJ"), Some(HandType::FiveOfAKind));
+		assert_eq!(classify(&ruleset, "QJJQ2"), Some(HandType::FourOfAKind));
+		assert_eq!(classify(&ruleset, "2233J"), Some(HandType::FullHouse));
+		assert_eq!(classify(&ruleset, "T55J5"), Some(HandType::FourOfAKind));
+		assert_eq!(classify(&ruleset, "2345J"), Some(HandType::OnePair));
+		assert_eq!(classify(&ruleset, "32T3K"), Some(HandType::OnePair));
+	}
+
+	#[test]
+	fn test_classify_straights() {
+		let ruleset = Ruleset::jokers().with_straights();
+		assert_eq!(classify(&ruleset, "23456"), Some(HandType::Straight));
+		assert_eq!(classify(&ruleset, "2345K"), Some(HandType::HighCard));
+		assert_eq!(classify(&ruleset, "22456"), Some(HandType::OnePair));
+		assert_eq!(classify(&ruleset, "TJQKA"), Some(HandType::Straight));
+		assert_eq!(classify(&ruleset, "2J45J"), Some(HandType::Straight));
+		// jokers are not part of the order, so the queen directly follows the ten
+		assert_eq!(classify(&ruleset, "9TQKA"), Some(HandType::Straight));
+
+		let cards = "2J45J".chars().map(|label| ruleset.card(label).unwrap());
+		let cards = cards.collect::<Vec<_>>();
+		assert_eq!(
+			ruleset.resolve_wilds(&cards, HandType::Straight),
+			vec!['3', '6']
+		);
+
+		// a straight beats three of a kind, but is only a high card without straights
+		let lines = ["23456 1", "22345 10", "33345 100"];
+		assert_eq!(
+			solve(lines, &Ruleset::standard()).unwrap(),
+			1 + 2 * 10 + 3 * 100
+		);
+		let ruleset = Ruleset::standard().with_straights();
+		assert_eq!(solve(lines, &ruleset).unwrap(), 10 + 2 * 100 + 3);
+	}
+
+	#[test]
+	fn test_classify_missing_hand_types() {
+		let ruleset = Ruleset {
+			hand_types: vec![HandType::OnePair, HandType::FiveOfAKind],
+			..Ruleset::standard()
+		};
+		assert_eq!(classify(&ruleset, "KKKKK"), Some(HandType::FiveOfAKind));
+		assert_eq!(classify(&ruleset, "KKKK2"), Some(HandType::OnePair));
+		assert_eq!(classify(&ruleset, "23456"), None);
+		assert!(parse_hand("23456 1", &ruleset).is_err());
+	}
+
+	#[test]
+	fn test_hand_size() {
+		let ruleset = Ruleset {
+			hand_size: 3,
+			..Ruleset::standard()
+		};
+		assert!(parse_hand("32T3K 765", &ruleset).is_err());
+		assert_eq!(
+			solve(["KKK 1", "AA2 10", "2AK 100"], &ruleset).unwrap(),
+			100 + 2 * 10 + 3
+		);
+	}
+
+	#[test]
+	fn test_resolve_wilds() {
+		let ruleset = Ruleset::jokers();
+		let resolve = |labels: &str| {
+			let cards = labels
+				.chars()
+				.map(|label| ruleset.card(label).unwrap())
+				.collect::<Vec<_>>();
+			let hand_type = ruleset.classify(&cards).unwrap();
+			String::from_iter(ruleset.resolve_wilds(&cards, hand_type))
+		};
+		assert_eq!(resolve("32T3K"), "");
+		assert_eq!(resolve("T55J5"), "5");
+		assert_eq!(resolve("KTJJT"), "TT");
+		assert_eq!(resolve("2233J"), "3");
+		assert_eq!(resolve("2345J"), "5");
+		assert_eq!(resolve("JJJJJ"), "AAAAA");
+		assert_eq!(resolve("JJJJ2"), "2222");
+	}
+
+	#[test]
+	fn test_sort_key() {
+		let ruleset = Ruleset::standard();
+		let hands = ["33332 1", "2AAAA 1", "77888 1", "77788 1"]
+			.map(|hand| parse_hand(hand, &ruleset).unwrap().sort_key);
+		assert!(hands[0] > hands[1]);
+		assert!(hands[1] > hands[2]);
+		assert!(hands[2] > hands[3]);
+	}
+
+	#[test]
+	fn test_explain() {
+		assert_eq!(
+			explain(EXAMPLE_LINES, &Ruleset::jokers()).unwrap(),
+			vec![
+				"32T3K OnePair: rank 1, wins 1 * 765 = 765",
+				"KK677 TwoPair: rank 2, wins 2 * 28 = 56",
+				"T55J5 FourOfAKind (wild cards as 5): rank 3, wins 3 * 684 = 2052",
+				"QQQJA FourOfAKind (wild cards as Q): rank 4, wins 4 * 483 = 1932",
+				"KTJJT FourOfAKind (wild cards as TT): rank 5, wins 5 * 220 = 1100",
+			]
+		);
+	}
+
+	#[test]
+	fn test_probabilities() {
+		let report = probabilities(EXAMPLE_LINES, &Ruleset::standard()).unwrap();
+		assert_eq!(
+			report.first().unwrap(),
+			"HighCard: 154440 / 371293 = 0.415952"
+		);
+		assert_eq!(
+			report.last().unwrap(),
+			"Expected winnings against random hands: 9596.0"
+		);
+	}
+
+	#[test]
+	fn test_parse_invalid_card() {
+		assert!(parse_hand("32T3X 765", &Ruleset::standard()).is_err());
+	}
 }
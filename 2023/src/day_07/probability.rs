@@ -0,0 +1,159 @@
+//! Exact hand type probabilities, found by enumerating every sequence of cards a ruleset allows
+
+use super::{HandType, Ruleset};
+
+/// How many card sequences make each hand type of a ruleset
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct HandTypeTable {
+	/// Hand types in the order of the ruleset, weakest first
+	pub(super) counts: Vec<(HandType, u64)>,
+	pub(super) total: u64,
+}
+
+impl HandTypeTable {
+	/// Classifies all `order.len() ^ hand_size` card sequences of the ruleset
+	pub(super) fn enumerate(ruleset: &Ruleset) -> anyhow::Result<Self> {
+		let labels = ruleset.order.len();
+		let total = u32::try_from(ruleset.hand_size)
+			.ok()
+			.and_then(|hand_size| (labels as u64).checked_pow(hand_size))
+			.ok_or(anyhow::anyhow!("too many card sequences to enumerate"))?;
+
+		let mut counts = ruleset
+			.hand_types
+			.iter()
+			.map(|&hand_type| (hand_type, 0))
+			.collect::<Vec<_>>();
+
+		let mut indices = vec![0; ruleset.hand_size];
+		let mut cards = indices
+			.iter()
+			.map(|&i| ruleset.card(ruleset.order[i]))
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		for _ in 0..total {
+			let hand_type = ruleset.classify(&cards).ok_or(anyhow::anyhow!(
+				"cards qualify for no hand type of the ruleset"
+			))?;
+			// this cannot panic because `classify` only returns hand types of the ruleset
+			let rank = ruleset.type_rank(&hand_type).unwrap();
+			counts[rank].1 += 1;
+
+			// advance to the next sequence like an odometer, last card first
+			for (index, card) in indices.iter_mut().zip(cards.iter_mut()).rev() {
+				*index = (*index + 1) % labels;
+				*card = ruleset.card(ruleset.order[*index])?;
+				if *index != 0 {
+					break;
+				}
+			}
+		}
+
+		Ok(HandTypeTable { counts, total })
+	}
+
+	pub(super) fn count(&self, hand_type: HandType) -> u64 {
+		self.counts
+			.iter()
+			.find(|&&(t, _)| t == hand_type)
+			.map_or(0, |&(_, count)| count)
+	}
+
+	pub(super) fn probability(&self, hand_type: HandType) -> f64 {
+		self.count(hand_type) as f64 / self.total as f64
+	}
+
+	/// Expected rank of a hand of `hand_type` among itself and `opponents` hands drawn uniformly
+	/// at random. The table does not tell the cards of a hand type apart, so an opponent of the
+	/// same hand type is a tie that the hand wins half of the time.
+	pub(super) fn expected_rank(&self, hand_type: HandType, opponents: usize) -> f64 {
+		let weaker = self
+			.counts
+			.iter()
+			.take_while(|&&(t, _)| t != hand_type)
+			.map(|&(_, count)| count)
+			.sum::<u64>();
+		let ties = self.count(hand_type);
+		let beaten = (weaker as f64 + ties as f64 / 2.0) / self.total as f64;
+		1.0 + opponents as f64 * beaten
+	}
+
+	/// Expected total winnings if every hand played against random hands instead of the others
+	pub(super) fn expected_winnings(&self, hands: &[(HandType, u64)]) -> f64 {
+		let opponents = hands.len().saturating_sub(1);
+		hands
+			.iter()
+			.map(|&(hand_type, bid)| bid as f64 * self.expected_rank(hand_type, opponents))
+			.sum()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_standard() {
+		let table = HandTypeTable::enumerate(&Ruleset::standard()).unwrap();
+		assert_eq!(table.total, 13u64.pow(5));
+		assert_eq!(
+			table.counts,
+			vec![
+				(HandType::HighCard, 13 * 12 * 11 * 10 * 9),
+				(HandType::OnePair, 13 * 220 * 60),
+				(HandType::TwoPair, 78 * 11 * 30),
+				(HandType::ThreeOfAKind, 13 * 66 * 20),
+				(HandType::FullHouse, 13 * 12 * 10),
+				(HandType::FourOfAKind, 13 * 12 * 5),
+				(HandType::FiveOfAKind, 13),
+			]
+		);
+		assert_eq!(table.probability(HandType::FiveOfAKind), 13.0 / 371293.0);
+	}
+
+	#[test]
+	fn test_jokers() {
+		let table = HandTypeTable::enumerate(&Ruleset::jokers()).unwrap();
+		assert_eq!(
+			table.counts.iter().map(|&(_, count)| count).sum::<u64>(),
+			table.total
+		);
+		// all natural cards are equal: 12 choices for every amount of jokers but five
+		assert_eq!(table.count(HandType::FiveOfAKind), 12 * 31 + 1);
+		// a single joker always pairs up with something
+		assert_eq!(table.count(HandType::HighCard), 12 * 11 * 10 * 9 * 8);
+		assert_eq!(table.count(HandType::Straight), 0);
+	}
+
+	#[test]
+	fn test_expected_winnings() {
+		// hands of two cards, each either `A` or `B`, so that `AA` and `BB` are pairs
+		let ruleset = Ruleset {
+			order: vec!['A', 'B'],
+			wild: Vec::new(),
+			hand_size: 2,
+			hand_types: vec![HandType::HighCard, HandType::OnePair],
+		};
+		let table = HandTypeTable::enumerate(&ruleset).unwrap();
+		// a pair beats half of the opponents and ties with the other half
+		assert_eq!(table.expected_rank(HandType::OnePair, 1), 1.75);
+		assert_eq!(table.expected_rank(HandType::HighCard, 1), 1.25);
+		let hands = [(HandType::OnePair, 3), (HandType::HighCard, 10)];
+		assert_eq!(table.expected_winnings(&hands), 3.0 * 1.75 + 10.0 * 1.25);
+	}
+
+	#[test]
+	fn test_expected_rank_of_random_hand() {
+		// a hand that is random itself is equally likely to end up at every rank
+		for ruleset in [Ruleset::standard(), Ruleset::jokers().with_straights()] {
+			let table = HandTypeTable::enumerate(&ruleset).unwrap();
+			let expected_rank = table
+				.counts
+				.iter()
+				.map(|&(hand_type, _)| {
+					table.probability(hand_type) * table.expected_rank(hand_type, 4)
+				})
+				.sum::<f64>();
+			assert!((expected_rank - 3.0).abs() < 1e-9);
+		}
+	}
+}
@@ -3,15 +3,24 @@ use std::collections::HashMap;
 const INPUT: &'static str = include_str!("../../inputs/2023/day_08.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u64> {
-	if part_two {
-		todo!();
+	match part_two {
+		false => solve_part_1(INPUT.lines()),
+		true => solve_part_2(INPUT.lines()),
 	}
-	Ok(solve_part_1(INPUT.lines()))
 }
 
-fn solve_part_1(lines: impl IntoIterator<Item = &'static str>) -> u64 {
+fn solve_part_1(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u64> {
 	let network = parse_network(lines);
-	network.count_steps("AAA", "ZZZ")
+	network
+		.count_steps("AAA", "ZZZ")
+		.ok_or(anyhow::anyhow!("`ZZZ` is not reachable from `AAA`"))
+}
+
+fn solve_part_2(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u64> {
+	let network = parse_network(lines);
+	network.count_ghost_steps().ok_or(anyhow::anyhow!(
+		"ghosts never stand on nodes ending with `Z` at the same time"
+	))
 }
 
 #[derive(Copy, Clone)]
@@ -36,34 +45,197 @@ struct Network {
 }
 
 impl Network {
-	fn next_node(&self, node: &str, direction: &Direction) -> &str {
+	fn next_node(&self, node: &str, direction: &Direction) -> Option<&str> {
+		let (left, right) = self.nodes.get(node)?;
 		match direction {
-			Direction::Left => &self.nodes.get(node).unwrap().0,
-			Direction::Right => &self.nodes.get(node).unwrap().1,
+			Direction::Left => Some(left),
+			Direction::Right => Some(right),
 		}
 	}
 
-	fn count_steps(&self, start_node: &str, target_node: &str) -> u64 {
-		self.count_steps_recursive(
-			start_node,
-			target_node,
-			self.directions.iter().cloned().cycle(),
-		)
+	fn walk<'a>(&'a self, start_node: &'a str) -> Walker<'a> {
+		Walker {
+			network: self,
+			node: start_node,
+			instruction: 0,
+		}
 	}
 
-	fn count_steps_recursive(
-		&self,
-		start_node: &str,
-		target_node: &str,
-		mut directions: impl Iterator<Item = Direction>,
-	) -> u64 {
+	fn count_steps(&self, start_node: &str, target_node: &str) -> Option<u64> {
 		if start_node == target_node {
-			return 0;
+			return Some(0);
+		}
+
+		// after visiting every (node, instruction) pair once, the walk can only repeat itself
+		let max_steps = self.nodes.len() * self.directions.len();
+		let steps = self
+			.walk(start_node)
+			.take(max_steps)
+			.position(|(node, _)| node == target_node)?;
+		Some(steps as u64 + 1)
+	}
+
+	fn count_ghost_steps(&self) -> Option<u64> {
+		let paths = self
+			.nodes
+			.keys()
+			.filter(|node| node.ends_with('A'))
+			.map(|node| GhostPath::trace(self, node))
+			.collect::<Option<Vec<_>>>()?;
+
+		// while at least one ghost has not entered its loop yet, only its prefix hits are candidates
+		let mut prefix_hits = paths
+			.iter()
+			.flat_map(|path| path.prefix_hits.iter().copied())
+			.collect::<Vec<_>>();
+		prefix_hits.sort_unstable();
+		if let Some(steps) = prefix_hits
+			.into_iter()
+			.find(|&steps| paths.iter().all(|path| path.is_on_target(steps)))
+		{
+			return Some(steps);
+		}
+
+		// from here on every ghost is inside its loop
+		let min_steps = paths.iter().map(|path| path.loop_start).max()?;
+		let congruences =
+			paths
+				.iter()
+				.try_fold(vec![Congruence::new(0, 1)], |congruences, path| {
+					let combined = congruences
+						.iter()
+						.flat_map(|a| path.congruences().filter_map(|b| a.combine(&b)))
+						.collect::<Vec<_>>();
+					(!combined.is_empty()).then_some(combined)
+				})?;
+
+		let steps = congruences
+			.iter()
+			.map(|congruence| congruence.smallest_at_least(min_steps.into()))
+			.min()?;
+		steps.try_into().ok()
+	}
+}
+
+struct Walker<'a> {
+	network: &'a Network,
+	node: &'a str,
+	instruction: usize,
+}
+
+impl<'a> Iterator for Walker<'a> {
+	/// The node after the step and the index of the instruction to follow next
+	type Item = (&'a str, usize);
+
+	fn next(&mut self) -> Option<Self::Item> {
+		let direction = self.network.directions.get(self.instruction)?;
+		self.node = self.network.next_node(self.node, direction)?;
+		self.instruction = (self.instruction + 1) % self.network.directions.len();
+		Some((self.node, self.instruction))
+	}
+}
+
+/// Where a single ghost is on a node ending with `Z`, described by the steps before it enters its
+/// loop and the steps within the first round of the loop.
+#[derive(Debug, PartialEq, Eq)]
+struct GhostPath {
+	prefix_hits: Vec<u64>,
+	loop_start: u64,
+	loop_length: u64,
+	loop_hits: Vec<u64>,
+}
+
+impl GhostPath {
+	fn trace(network: &Network, start_node: &str) -> Option<Self> {
+		let mut visited = HashMap::<(&str, usize), u64>::new();
+		let mut hits = Vec::new();
+		let mut state = (start_node, 0);
+		let mut walker = network.walk(start_node);
+		let mut steps = 0;
+
+		loop {
+			if let Some(&loop_start) = visited.get(&state) {
+				let (prefix_hits, loop_hits) = hits.iter().partition(|&&hit| hit < loop_start);
+				return Some(GhostPath {
+					prefix_hits,
+					loop_start,
+					loop_length: steps - loop_start,
+					loop_hits,
+				});
+			}
+			visited.insert(state, steps);
+			if state.0.ends_with('Z') {
+				hits.push(steps);
+			}
+			state = walker.next()?;
+			steps += 1;
+		}
+	}
+
+	fn is_on_target(&self, steps: u64) -> bool {
+		if steps < self.loop_start {
+			return self.prefix_hits.contains(&steps);
+		}
+		let steps_in_loop = (steps - self.loop_start) % self.loop_length;
+		self.loop_hits.contains(&(self.loop_start + steps_in_loop))
+	}
+
+	fn congruences(&self) -> impl Iterator<Item = Congruence> + '_ {
+		self.loop_hits
+			.iter()
+			.map(|&hit| Congruence::new(hit.into(), self.loop_length.into()))
+	}
+}
+
+/// All step counts `n` with `n ≡ residue (mod modulus)`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Congruence {
+	residue: u128,
+	modulus: u128,
+}
+
+impl Congruence {
+	fn new(residue: u128, modulus: u128) -> Self {
+		Congruence {
+			residue: residue % modulus,
+			modulus,
+		}
+	}
+
+	/// Chinese remainder theorem for moduli that are not necessarily coprime.
+	/// Returns `None` if no step count satisfies both congruences.
+	fn combine(&self, other: &Self) -> Option<Self> {
+		let (gcd, inverse, _) = extended_gcd(self.modulus as i128, other.modulus as i128);
+		let gcd = gcd as u128;
+		let difference = other.residue as i128 - self.residue as i128;
+		if difference % gcd as i128 != 0 {
+			return None;
+		}
+
+		let other_modulus = (other.modulus / gcd) as i128;
+		let factor = (difference / gcd as i128 % other_modulus * inverse).rem_euclid(other_modulus);
+		let modulus = self.modulus / gcd * other.modulus;
+		Some(Congruence::new(
+			self.residue + self.modulus * factor as u128,
+			modulus,
+		))
+	}
+
+	fn smallest_at_least(&self, min: u128) -> u128 {
+		if self.residue >= min {
+			return self.residue;
 		}
+		self.residue + (min - self.residue).div_ceil(self.modulus) * self.modulus
+	}
+}
 
-		let next_node = self.next_node(start_node, &directions.next().unwrap());
-		1 + self.count_steps_recursive(next_node, target_node, directions)
+/// Returns `(gcd, x, y)` such that `a * x + b * y = gcd`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+	if b == 0 {
+		return (a, 1, 0);
 	}
+	let (gcd, x, y) = extended_gcd(b, a % b);
+	(gcd, y, x - a / b * y)
 }
 
 fn parse_network(input: impl IntoIterator<Item = &'static str>) -> Network {
@@ -113,7 +285,7 @@ mod tests {
 			"GGG = (GGG, GGG)",
 			"ZZZ = (ZZZ, ZZZ)",
 		];
-		assert_eq!(solve_part_1(network), 2);
+		assert_eq!(solve_part_1(network).unwrap(), 2);
 	}
 
 	#[test]
@@ -125,6 +297,143 @@ mod tests {
 			"BBB = (AAA, ZZZ)",
 			"ZZZ = (ZZZ, ZZZ)",
 		];
-		assert_eq!(solve_part_1(network), 6);
+		assert_eq!(solve_part_1(network).unwrap(), 6);
+	}
+
+	/// Walks every ghost at the same time, one step after another
+	fn simulate_ghost_steps(network: &Network, max_steps: u64) -> Option<u64> {
+		let mut ghosts = network
+			.nodes
+			.keys()
+			.filter(|node| node.ends_with('A'))
+			.map(|node| network.walk(node))
+			.collect::<Vec<_>>();
+		for steps in 0..=max_steps {
+			if ghosts.iter().all(|ghost| ghost.node.ends_with('Z')) {
+				return Some(steps);
+			}
+			ghosts
+				.iter_mut()
+				.try_for_each(|ghost| ghost.next().map(|_| ()))?;
+		}
+		None
+	}
+
+	#[test]
+	fn test_long_walk() {
+		let mut directions = vec![Direction::Left; 999_999];
+		directions.push(Direction::Right);
+		let network = Network {
+			directions,
+			nodes: HashMap::from([
+				("AAA".to_string(), ("AAA".to_string(), "ZZZ".to_string())),
+				("ZZZ".to_string(), ("ZZZ".to_string(), "ZZZ".to_string())),
+			]),
+		};
+		assert_eq!(network.count_steps("AAA", "ZZZ"), Some(1_000_000));
+	}
+
+	#[test]
+	fn test_unreachable() {
+		let network = ["L", "", "AAA = (AAA, ZZZ)", "ZZZ = (ZZZ, ZZZ)"];
+		assert!(solve_part_1(network).is_err());
+	}
+
+	#[test]
+	fn test_example_part_2() {
+		let network = [
+			"LR",
+			"",
+			"11A = (11B, XXX)",
+			"11B = (XXX, 11Z)",
+			"11Z = (11B, XXX)",
+			"22A = (22B, XXX)",
+			"22B = (22C, 22C)",
+			"22C = (22Z, 22Z)",
+			"22Z = (22B, 22B)",
+			"XXX = (XXX, XXX)",
+		];
+		assert_eq!(solve_part_2(network).unwrap(), 6);
+	}
+
+	#[test]
+	fn test_trace() {
+		let network = parse_network([
+			"L",
+			"",
+			"AAA = (ABB, ABB)",
+			"ABB = (ABZ, ABZ)",
+			"ABZ = (ABC, ABC)",
+			"ABC = (ABB, ABB)",
+		]);
+		assert_eq!(
+			GhostPath::trace(&network, "AAA"),
+			Some(GhostPath {
+				prefix_hits: vec![],
+				loop_start: 1,
+				loop_length: 3,
+				loop_hits: vec![2],
+			})
+		);
+	}
+
+	#[test]
+	fn test_unaligned_loops() {
+		// the first ghost is on `ABZ` after 2, 5, 8, ... steps, the second one after 1, 3, 5, ... steps
+		let network = parse_network([
+			"L",
+			"",
+			"AAA = (ABB, ABB)",
+			"ABB = (ABZ, ABZ)",
+			"ABZ = (ABC, ABC)",
+			"ABC = (ABB, ABB)",
+			"BBA = (BBZ, BBZ)",
+			"BBZ = (BBB, BBB)",
+			"BBB = (BBZ, BBZ)",
+		]);
+		assert_eq!(network.count_ghost_steps(), Some(5));
+		assert_eq!(simulate_ghost_steps(&network, 100), Some(5));
+	}
+
+	#[test]
+	fn test_prefix_hit() {
+		// the second ghost is only on a node ending with `Z` once before getting stuck on `BBB`
+		let network = parse_network([
+			"L",
+			"",
+			"AAA = (AAZ, AAZ)",
+			"AAZ = (AAA, AAA)",
+			"BBA = (BBZ, BBZ)",
+			"BBZ = (BBB, BBB)",
+			"BBB = (BBB, BBB)",
+		]);
+		assert_eq!(network.count_ghost_steps(), Some(1));
+		assert_eq!(simulate_ghost_steps(&network, 100), Some(1));
+	}
+
+	#[test]
+	fn test_never_aligned() {
+		// the first ghost is on `AAZ` after an odd amount of steps, the second one after an even amount
+		let network = parse_network([
+			"L",
+			"",
+			"AAA = (AAZ, AAZ)",
+			"AAZ = (AAB, AAB)",
+			"AAB = (AAZ, AAZ)",
+			"BBA = (BBB, BBB)",
+			"BBB = (BBZ, BBZ)",
+			"BBZ = (BBB, BBB)",
+		]);
+		assert_eq!(network.count_ghost_steps(), None);
+		assert_eq!(simulate_ghost_steps(&network, 100), None);
+	}
+
+	#[test]
+	fn test_combine_congruences() {
+		let a = Congruence::new(2, 4);
+		let b = Congruence::new(4, 6);
+		assert_eq!(a.combine(&b), Some(Congruence::new(10, 12)));
+		assert_eq!(a.combine(&Congruence::new(1, 6)), None);
+		assert_eq!(Congruence::new(10, 12).smallest_at_least(11), 22);
 	}
 }
@@ -1,46 +1,108 @@
+use std::{fmt::Debug, str::FromStr};
+
+use anyhow::{anyhow, Context};
+use num_bigint::BigInt;
+use num_traits::{CheckedAdd, CheckedSub, Zero};
+
 const INPUT: &'static str = include_str!("../../inputs/2023/day_09.txt");
 
-pub fn main(part_two: bool) -> anyhow::Result<i64> {
-	solve(INPUT.lines(), part_two)
+pub fn main(part_two: bool) -> anyhow::Result<BigInt> {
+	let direction = match part_two {
+		false => Direction::Forward,
+		true => Direction::Backward,
+	};
+	let steps = crate::option("Steps to extrapolate (1)")?.unwrap_or(1);
+	let big = crate::option("Use big integers? (false)")?.unwrap_or(false);
+
+	match big {
+		false => solve::<i64>(INPUT.lines(), direction, steps).map(BigInt::from),
+		true => solve::<BigInt>(INPUT.lines(), direction, steps),
+	}
+}
+
+/// Numbers that histories can be extrapolated with.
+trait Value: Clone + Debug + FromStr + CheckedAdd + CheckedSub + Zero {}
+
+impl<T> Value for T where T: Clone + Debug + FromStr + CheckedAdd + CheckedSub + Zero {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+	Forward,
+	Backward,
 }
 
-fn solve(lines: impl IntoIterator<Item = &'static str>, suffix: bool) -> anyhow::Result<i64> {
-	let result = lines
+/// Sums the values `steps` steps beyond each history.
+fn solve<'a, V: Value>(
+	lines: impl IntoIterator<Item = &'a str>,
+	direction: Direction,
+	steps: usize,
+) -> anyhow::Result<V>
+where
+	V::Err: std::error::Error + Send + Sync + 'static,
+{
+	lines
 		.into_iter()
-		.map(|line| parse_history(&line))
-		.map(|history| Ok(extrapolate(&history?, !suffix)?))
-		.sum::<anyhow::Result<_>>()?;
-
-	Ok(result)
+		.enumerate()
+		.try_fold(V::zero(), |sum, (i, line)| {
+			let value = parse_history::<V>(line)
+				.and_then(|history| extrapolate(&history, direction, steps))
+				.with_context(|| format!("history on line {}: {line}", i + 1))?
+				.pop()
+				.unwrap_or(V::zero());
+			sum.checked_add(&value)
+				.ok_or(anyhow!("sum overflows at line {}", i + 1))
+		})
 }
 
-fn parse_history(input: &str) -> anyhow::Result<Vec<i64>> {
+fn parse_history<V: Value>(input: &str) -> anyhow::Result<Vec<V>>
+where
+	V::Err: std::error::Error + Send + Sync + 'static,
+{
 	let history = input
 		.split_whitespace()
-		.map(|s| s.parse::<i64>())
+		.map(|s| s.parse::<V>())
 		.collect::<Result<Vec<_>, _>>()?;
 	Ok(history)
 }
 
-fn extrapolate(history: &Vec<i64>, suffix: bool) -> anyhow::Result<i64> {
-	if history.iter().all(|&n| n == 0) {
-		return Ok(0);
-	}
+/// Returns the next `steps` values of `history` in the given direction, nearest first.
+fn extrapolate<V: Value>(
+	history: &[V],
+	direction: Direction,
+	steps: usize,
+) -> anyhow::Result<Vec<V>> {
+	// the values on the extrapolated side of each row of differences, down to the last row that
+	// is not all zeros
+	let mut edges = Vec::new();
+	let mut row = history.to_vec();
+	while !row.iter().all(V::is_zero) {
+		let edge = match direction {
+			Direction::Forward => row.last(),
+			Direction::Backward => row.first(),
+		};
+		// this cannot panic because `row` contains a non-zero value
+		edges.push(edge.unwrap().clone());
 
-	let diff = history
-		.windows(2)
-		.map(|w| w[1] - w[0])
-		.collect::<Vec<i64>>();
-
-	let result = if suffix {
-		// this cannot panic because `history.iter().all(|&n| n == 0)` implies `history.len() > 1`
-		history.last().unwrap() + extrapolate(&diff, true)?
-	} else {
-		// this cannot panic because `history.iter().all(|&n| n == 0)` implies `history.len() > 1`
-		history.first().unwrap() - extrapolate(&diff, false)?
-	};
+		row = row
+			.windows(2)
+			.map(|w| w[1].checked_sub(&w[0]))
+			.collect::<Option<_>>()
+			.ok_or(anyhow!("differences overflow"))?;
+	}
 
-	Ok(result)
+	(0..steps)
+		.map(|_| {
+			// the row below the last edge is all zeros, so the last edge stays the same
+			for k in (0..edges.len().saturating_sub(1)).rev() {
+				let next = match direction {
+					Direction::Forward => edges[k].checked_add(&edges[k + 1]),
+					Direction::Backward => edges[k].checked_sub(&edges[k + 1]),
+				};
+				edges[k] = next.ok_or(anyhow!("extrapolation overflows"))?;
+			}
+			Ok(edges.first().cloned().unwrap_or(V::zero()))
+		})
+		.collect()
 }
 
 #[cfg(test)]
@@ -53,7 +115,7 @@ mod tests {
 		#[test]
 		fn test_example() {
 			let network = ["0 3 6 9 12 15", "1 3 6 10 15 21", "10 13 16 21 30 45"];
-			assert_eq!(solve(network, false).unwrap(), 114);
+			assert_eq!(solve::<i64>(network, Direction::Forward, 1).unwrap(), 114);
 		}
 	}
 
@@ -63,7 +125,46 @@ mod tests {
 		#[test]
 		fn test_example() {
 			let network = ["10 13 16 21 30 45"];
-			assert_eq!(solve(network, true).unwrap(), 5);
+			assert_eq!(solve::<i64>(network, Direction::Backward, 1).unwrap(), 5);
 		}
 	}
+
+	#[test]
+	fn test_extrapolate_steps() {
+		let history = [1i64, 3, 6, 10, 15, 21];
+		assert_eq!(
+			extrapolate(&history, Direction::Forward, 3).unwrap(),
+			[28, 36, 45]
+		);
+		assert_eq!(
+			extrapolate(&history, Direction::Backward, 3).unwrap(),
+			[0, 0, 1]
+		);
+		assert_eq!(extrapolate(&history, Direction::Forward, 0).unwrap(), []);
+		assert_eq!(
+			extrapolate(&[0i64, 0], Direction::Backward, 2).unwrap(),
+			[0, 0]
+		);
+		assert_eq!(extrapolate(&[7i64], Direction::Forward, 2).unwrap(), [7, 7]);
+	}
+
+	#[test]
+	fn test_overflow_names_line() {
+		let network = ["1 2 3", "0 9223372036854775807"];
+		let err = solve::<i64>(network, Direction::Forward, 1).unwrap_err();
+		assert_eq!(err.to_string(), "history on line 2: 0 9223372036854775807");
+
+		let network = ["-9223372036854775808 9223372036854775807"];
+		let err = solve::<i64>(network, Direction::Backward, 1).unwrap_err();
+		assert_eq!(err.root_cause().to_string(), "differences overflow");
+	}
+
+	#[test]
+	fn test_big_integers() {
+		let network = ["0 9223372036854775807"];
+		assert_eq!(
+			solve::<BigInt>(network, Direction::Forward, 2).unwrap(),
+			BigInt::from(i64::MAX) * 3
+		);
+	}
 }
@@ -3,16 +3,36 @@ use std::collections::{HashMap, HashSet};
 const INPUT: &'static str = include_str!("../../inputs/2023/day_10.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u32> {
-	if part_two {
-		todo!();
+	if crate::option("Print loops besides the main loop? (false)")?.unwrap_or(false) {
+		let maze = Maze::parse(INPUT.lines())?;
+		for path in maze.other_loops() {
+			println!("Loop of {} pipes starting at {:?}", path.len(), path[0]);
+		}
+	}
+
+	match part_two {
+		false => Ok(solve_part_1(INPUT.lines())),
+		true => solve_part_2(INPUT.lines()),
 	}
-	Ok(solve_part_1(INPUT.lines()))
 }
 
 fn solve_part_1(lines: impl IntoIterator<Item = &'static str>) -> u32 {
 	Maze::parse(lines).unwrap().max_distance_from_start()
 }
 
+fn solve_part_2(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u32> {
+	let maze = Maze::parse(lines)?;
+	let by_area = maze.enclosed_tiles_by_area();
+	let by_parity = maze.enclosed_tiles_by_parity();
+	anyhow::ensure!(
+		by_area == by_parity,
+		"Pick's theorem counts {} enclosed tiles, but the scanline counts {}",
+		by_area,
+		by_parity
+	);
+	Ok(by_area)
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 enum Direction {
 	Up,
@@ -21,7 +41,7 @@ enum Direction {
 	Right,
 }
 
-#[derive(PartialEq, Eq, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 struct Position(usize, usize);
 
 struct Pipe([Direction; 2]);
@@ -29,6 +49,8 @@ struct Pipe([Direction; 2]);
 struct Maze {
 	start_position: Position,
 	pipes: HashMap<Position, Pipe>,
+	width: usize,
+	height: usize,
 }
 
 struct MazeIterator<'a> {
@@ -64,6 +86,11 @@ impl Position {
 }
 
 impl Pipe {
+	fn is_corner(&self) -> bool {
+		let [this, other] = &self.0;
+		*this != other.opposite()
+	}
+
 	fn exclude(&self, direction: &Direction) -> anyhow::Result<&Direction> {
 		match &self.0 {
 			[this, other] if this == direction => Ok(other),
@@ -72,25 +99,33 @@ impl Pipe {
 		}
 	}
 
-	fn infer_from_neighbors(
+	/// Every pipe whose both ends connect to a neighboring pipe, even if that neighbor is not part
+	/// of a loop
+	fn candidates_from_neighbors(
 		pipes: &HashMap<Position, Pipe>,
 		position: &Position,
-	) -> anyhow::Result<Pipe> {
-		let connections = Direction::all().into_iter().filter(|direction| {
-			let Some(next) = &position.next(direction) else {
-				return false;
-			};
-			pipes
-				.get(next)
-				.is_some_and(|pipe| pipe.0.contains(&direction.opposite()))
-		});
-
-		let connections: [Direction; 2] = connections
-			.collect::<Vec<Direction>>()
-			.try_into()
-			.or(Err(anyhow::anyhow!("Amount of connections is not 2")))?;
-
-		Ok(Pipe(connections))
+	) -> Vec<Pipe> {
+		let connections = Direction::all()
+			.into_iter()
+			.filter(|direction| {
+				let Some(next) = &position.next(direction) else {
+					return false;
+				};
+				pipes
+					.get(next)
+					.is_some_and(|pipe| pipe.0.contains(&direction.opposite()))
+			})
+			.collect::<Vec<Direction>>();
+
+		connections
+			.iter()
+			.enumerate()
+			.flat_map(|(i, this)| {
+				connections[i + 1..]
+					.iter()
+					.map(|other| Pipe([this.clone(), other.clone()]))
+			})
+			.collect()
 	}
 }
 
@@ -131,28 +166,136 @@ impl Maze {
 	fn parse(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<Maze> {
 		let mut start_position = None;
 		let mut pipes = HashMap::new();
-
-		lines.into_iter().enumerate().for_each(|(y, line)| {
-			line.chars().enumerate().for_each(|(x, char)| match char {
-				'.' => (),
-				'S' => start_position = Some(Position(x, y)),
-				_ => {
-					pipes.insert(Position(x, y), Pipe::try_from(char).unwrap());
+		let mut width = 0;
+		let mut height = 0;
+
+		for (y, line) in lines.into_iter().enumerate() {
+			width = width.max(line.chars().count());
+			height = y + 1;
+			for (x, char) in line.chars().enumerate() {
+				match char {
+					'.' => (),
+					'S' => start_position = Some(Position(x, y)),
+					_ => {
+						let pipe = Pipe::try_from(char).map_err(|error| {
+							anyhow::anyhow!("{} '{}' at ({}, {})", error, char, x, y)
+						})?;
+						pipes.insert(Position(x, y), pipe);
+					}
 				}
-			});
-		});
+			}
+		}
 
 		let start_position = start_position.ok_or(anyhow::anyhow!("No start position"))?;
+		let candidates = Pipe::candidates_from_neighbors(&pipes, &start_position);
 
-		pipes.insert(
-			start_position.clone(),
-			Pipe::infer_from_neighbors(&pipes, &start_position)?,
-		);
-
-		Ok(Maze {
+		let mut maze = Maze {
 			start_position,
 			pipes,
-		})
+			width,
+			height,
+		};
+
+		// junk pipes may connect to the start position as well, so try until one closes the loop
+		for candidate in candidates {
+			maze.pipes.insert(maze.start_position.clone(), candidate);
+			if maze.trace_loop(&maze.start_position).is_some() {
+				return Ok(maze);
+			}
+		}
+
+		Err(anyhow::anyhow!("Start position is not part of a loop"))
+	}
+
+	/// Follows the pipe at `start` until it either arrives back at `start` or runs into a pipe that
+	/// does not connect back. Returns the positions in walking order, beginning at `start`.
+	fn trace_loop(&self, start: &Position) -> Option<Vec<Position>> {
+		let start_pipe = self.pipes.get(start)?;
+		let mut iterator = MazeIterator {
+			maze: self,
+			position: start.clone(),
+			entered_from: start_pipe.0[0].clone(),
+		};
+
+		let mut path = vec![start.clone()];
+		while let Some(position) = iterator.next() {
+			if position == *start {
+				return (iterator.entered_from == start_pipe.0[0]).then_some(path);
+			}
+			if path.len() > self.pipes.len() {
+				return None;
+			}
+			path.push(position);
+		}
+		None
+	}
+
+	/// Every position of the loop in walking order, beginning at the start position
+	fn loop_path(&self) -> Vec<Position> {
+		// this cannot panic because `Maze::parse` only accepts start positions on a loop
+		self.trace_loop(&self.start_position).unwrap()
+	}
+
+	/// Every closed loop besides the one through the start position, ordered by their top left
+	/// position
+	fn other_loops(&self) -> Vec<Vec<Position>> {
+		let mut visited = self.loop_path().into_iter().collect::<HashSet<_>>();
+		let mut positions = self.pipes.keys().collect::<Vec<_>>();
+		positions.sort_unstable_by_key(|position| (position.1, position.0));
+
+		let mut loops = Vec::new();
+		for position in positions {
+			if visited.contains(position) {
+				continue;
+			}
+			match self.trace_loop(position) {
+				Some(path) => {
+					visited.extend(path.iter().cloned());
+					loops.push(path);
+				}
+				None => {
+					visited.insert(position.clone());
+				}
+			}
+		}
+		loops
+	}
+
+	/// The corners of the loop in walking order
+	fn loop_vertices(&self) -> Vec<Position> {
+		self.loop_path()
+			.into_iter()
+			.filter(|position| self.pipes.get(position).is_some_and(Pipe::is_corner))
+			.collect()
+	}
+
+	/// Applies Pick's theorem `A = i + b / 2 - 1` to the area enclosed by the loop
+	fn enclosed_tiles_by_area(&self) -> u32 {
+		let boundary = self.loop_path().len() as u64;
+		let area = shoelace_area(&self.loop_vertices());
+		(area + 1 - boundary / 2) as u32
+	}
+
+	/// Scans every row from left to right and counts the tiles after an odd amount of loop
+	/// crossings. Only pipes reaching up count as a crossing, so that walking along a horizontal
+	/// segment is only counted as a crossing if it leaves in the opposite direction it came from.
+	fn enclosed_tiles_by_parity(&self) -> u32 {
+		let path = self.loop_path().into_iter().collect::<HashSet<_>>();
+		let mut count = 0;
+		for y in 0..self.height {
+			let mut inside = false;
+			for x in 0..self.width {
+				let position = Position(x, y);
+				if !path.contains(&position) {
+					count += inside as u32;
+					continue;
+				}
+				if self.pipes[&position].0.contains(&Direction::Up) {
+					inside = !inside;
+				}
+			}
+		}
+		count
 	}
 
 	fn max_distance_from_start(&self) -> u32 {
@@ -173,12 +316,23 @@ impl Maze {
 	}
 }
 
+/// Area of a simple polygon given its vertices in order
+fn shoelace_area(vertices: &[Position]) -> u64 {
+	let next_vertices = vertices.iter().cycle().skip(1);
+	let double_area = vertices
+		.iter()
+		.zip(next_vertices)
+		.map(|(a, b)| (a.0 * b.1) as i64 - (b.0 * a.1) as i64)
+		.sum::<i64>();
+	double_area.unsigned_abs() / 2
+}
+
 impl Iterator for MazeIterator<'_> {
 	type Item = Position;
 
 	fn next(&mut self) -> Option<Self::Item> {
 		let pipe = self.maze.pipes.get(&self.position)?;
-		let next_direction = pipe.exclude(&self.entered_from).unwrap();
+		let next_direction = pipe.exclude(&self.entered_from).ok()?;
 		let next_position = self.position.next(next_direction)?;
 		self.entered_from = next_direction.opposite();
 		self.position = next_position.clone();
@@ -201,4 +355,172 @@ mod tests {
 		let graph = ["..F7.", ".FJ|.", "SJ.L7", "|F--J", "LJ..."];
 		assert_eq!(solve_part_1(graph), 8);
 	}
+
+	#[test]
+	fn test_loop_path() {
+		let graph = [".....", ".S-7.", ".|.|.", ".L-J.", "....."];
+		let maze = Maze::parse(graph).unwrap();
+		assert_eq!(
+			maze.loop_path(),
+			vec![
+				Position(1, 1),
+				Position(2, 1),
+				Position(3, 1),
+				Position(3, 2),
+				Position(3, 3),
+				Position(2, 3),
+				Position(1, 3),
+				Position(1, 2),
+			]
+		);
+		assert_eq!(
+			maze.loop_vertices(),
+			vec![
+				Position(1, 1),
+				Position(3, 1),
+				Position(3, 3),
+				Position(1, 3),
+			]
+		);
+	}
+
+	#[test]
+	fn test_parse_unknown_character() {
+		let graph = [".....", ".S-7.", ".|X|.", ".L-J.", "....."];
+		assert!(Maze::parse(graph).is_err());
+	}
+
+	#[test]
+	fn test_parse_without_loop() {
+		let graph = [".....", ".S-7.", ".|...", ".L-J.", "....."];
+		assert!(Maze::parse(graph).is_err());
+	}
+
+	#[test]
+	fn test_junk_connected_to_start() {
+		let graph = ["F7.F7", "SJ.LJ", "|.-.."];
+		let maze = Maze::parse(graph).unwrap();
+		assert_eq!(
+			maze.loop_path(),
+			vec![
+				Position(0, 1),
+				Position(1, 1),
+				Position(1, 0),
+				Position(0, 0),
+			]
+		);
+		assert_eq!(maze.max_distance_from_start(), 2);
+	}
+
+	#[test]
+	fn test_other_loops() {
+		let graph = ["F7.F7", "SJ.LJ", "|.-..", "F-7F7", "L-JLJ"];
+		let maze = Maze::parse(graph).unwrap();
+		assert_eq!(
+			maze.other_loops(),
+			vec![
+				vec![
+					Position(3, 0),
+					Position(4, 0),
+					Position(4, 1),
+					Position(3, 1),
+				],
+				vec![
+					Position(0, 3),
+					Position(1, 3),
+					Position(2, 3),
+					Position(2, 4),
+					Position(1, 4),
+					Position(0, 4),
+				],
+				vec![
+					Position(3, 3),
+					Position(4, 3),
+					Position(4, 4),
+					Position(3, 4),
+				],
+			]
+		);
+	}
+
+	#[test]
+	fn test_shoelace_area() {
+		let vertices = [
+			Position(1, 1),
+			Position(3, 1),
+			Position(3, 3),
+			Position(1, 3),
+		];
+		assert_eq!(shoelace_area(&vertices), 4);
+	}
+
+	mod part_2 {
+		use super::*;
+
+		#[test]
+		fn test_example() {
+			let graph = [
+				"...........",
+				".S-------7.",
+				".|F-----7|.",
+				".||.....||.",
+				".||.....||.",
+				".|L-7.F-J|.",
+				".|..|.|..|.",
+				".L--J.L--J.",
+				"...........",
+			];
+			assert_eq!(solve_part_2(graph).unwrap(), 4);
+		}
+
+		#[test]
+		fn test_squeezed_example() {
+			let graph = [
+				"..........",
+				".S------7.",
+				".|F----7|.",
+				".||....||.",
+				".||....||.",
+				".|L-7F-J|.",
+				".|..||..|.",
+				".L--JL--J.",
+				"..........",
+			];
+			assert_eq!(solve_part_2(graph).unwrap(), 4);
+		}
+
+		#[test]
+		fn test_larger_example() {
+			let graph = [
+				".F----7F7F7F7F-7....",
+				".|F--7||||||||FJ....",
+				".||.FJ||||||||L7....",
+				"FJL7L7LJLJ||LJ.L-7..",
+				"L--J.L7...LJS7F-7L7.",
+				"....F-J..F7FJ|L7L7L7",
+				"....L7.F7||L7|.L7L7|",
+				".....|FJLJ|FJ|F7|.LJ",
+				"....FJL-7.||.||||...",
+				"....L---J.LJ.LJLJ...",
+			];
+			assert_eq!(solve_part_2(graph).unwrap(), 8);
+		}
+
+		#[test]
+		fn test_junk_example() {
+			let graph = [
+				"FF7FSF7F7F7F7F7F---7",
+				"L|LJ||||||||||||F--J",
+				"FL-7LJLJ||||||LJL-77",
+				"F--JF--7||LJLJ7F7FJ-",
+				"L---JF-JLJ.||-FJLJJ7",
+				"|F|F-JF---7F7-L7L|7|",
+				"|FFJF7L7F-JF7|JL---7",
+				"7-L-JL7||F7|L7F-7F7|",
+				"L.L7LFJ|||||FJL7||LJ",
+				"L7JLJL-JLJLJL--JLJ.L",
+			];
+			assert_eq!(solve_part_2(graph).unwrap(), 10);
+		}
+	}
 }
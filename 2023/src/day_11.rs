@@ -1,42 +1,144 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, str::FromStr};
+
+use anyhow::anyhow;
 
 const INPUT: &'static str = include_str!("../../inputs/2023/day_11.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u64> {
 	let expansion_factor = if !part_two { 2 } else { 1000000 };
-	solve(INPUT.lines(), expansion_factor)
+	let row_factor = crate::option(&format!("Empty row factor ({expansion_factor})"))?
+		.unwrap_or(expansion_factor);
+	let column_factor = crate::option(&format!("Empty column factor ({expansion_factor})"))?
+		.unwrap_or(expansion_factor);
+	let metric = crate::option("Metric: manhattan, chebyshev or euclidean (manhattan)")?
+		.unwrap_or(Metric::Manhattan);
+
+	let universe = parse_universe(INPUT.lines()).expand(row_factor, column_factor);
+
+	if crate::option("Print pair distances? (false)")?.unwrap_or(false) {
+		for (a, b, distance) in universe.pair_distances(metric) {
+			println!("{a:?} - {b:?}: {distance}");
+		}
+	}
+	let k = crate::option("Nearest galaxies to list for each galaxy (0)")?.unwrap_or(0);
+	if k > 0 {
+		for galaxy in universe.sorted_galaxies() {
+			let nearest = universe.nearest(galaxy, k, metric);
+			println!("{galaxy:?}: {nearest:?}");
+		}
+	}
+
+	universe.distance_sum(metric)
 }
 
-fn solve(
-	lines: impl IntoIterator<Item = &'static str>,
-	expansion_factor: u64,
-) -> anyhow::Result<u64> {
-	let universe = parse_universe(lines).expand(expansion_factor);
-	let galaxies = universe.galaxies.iter();
-	let distance_sum = galaxies
-		.clone()
-		.enumerate()
-		.map(|(i, &a)| {
-			galaxies
-				.clone()
-				.skip(i + 1)
-				.map(|&b| universe.distance(a, b))
-				.sum::<u64>()
-		})
-		.sum::<u64>();
-	Ok(distance_sum)
+type Galaxy = (u64, u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Metric {
+	Manhattan,
+	Chebyshev,
+	Euclidean,
+}
+
+impl Metric {
+	fn distance(&self, a: Galaxy, b: Galaxy) -> f64 {
+		let (rows, columns) = (a.0.abs_diff(b.0) as f64, a.1.abs_diff(b.1) as f64);
+		match self {
+			Metric::Manhattan => rows + columns,
+			Metric::Chebyshev => rows.max(columns),
+			Metric::Euclidean => rows.hypot(columns),
+		}
+	}
+}
+
+impl FromStr for Metric {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.to_lowercase().as_str() {
+			"manhattan" => Ok(Metric::Manhattan),
+			"chebyshev" => Ok(Metric::Chebyshev),
+			"euclidean" => Ok(Metric::Euclidean),
+			_ => Err(anyhow!("unknown metric \"{s}\"")),
+		}
+	}
 }
 
 #[derive(Debug, PartialEq)]
 struct Universe {
-	galaxies: HashSet<(u64, u64)>,
+	galaxies: HashSet<Galaxy>,
 	width: u64,
 	height: u64,
 }
 
 impl Universe {
-	fn distance(&self, a: (u64, u64), b: (u64, u64)) -> u64 {
-		(a.0 as u64).abs_diff(b.0 as u64) + (a.1 as u64).abs_diff(b.1 as u64)
+	fn sorted_galaxies(&self) -> Vec<Galaxy> {
+		let mut galaxies = self.galaxies.iter().copied().collect::<Vec<_>>();
+		galaxies.sort_unstable();
+		galaxies
+	}
+
+	/// Sums the distances between every pair of galaxies. Euclidean distances are not integers, so
+	/// only their individual values are available through [`Universe::pair_distances`].
+	///
+	/// For Manhattan distances the rows and columns contribute independently. Along one axis, a
+	/// galaxy at sorted index `i` lies after `i` others, so it adds its coordinate `i` times and
+	/// the sum of the coordinates before it is subtracted once. Chebyshev distances become
+	/// Manhattan distances halved once the grid is rotated by 45 degrees.
+	fn distance_sum(&self, metric: Metric) -> anyhow::Result<u64> {
+		let axes = |axis: fn(Galaxy) -> u64| {
+			axis_distance_sum(self.galaxies.iter().copied().map(axis).collect())
+		};
+		match metric {
+			Metric::Manhattan => Ok(axes(|(row, _)| row) + axes(|(_, column)| column)),
+			Metric::Chebyshev => {
+				// `column < width`, so the anti-diagonal index cannot underflow
+				let width = self.width;
+				let diagonals = axes(|(row, column)| row + column);
+				let anti_diagonals = axis_distance_sum(
+					self.galaxies
+						.iter()
+						.map(|&(row, column)| row + width - column)
+						.collect(),
+				);
+				Ok((diagonals + anti_diagonals) / 2)
+			}
+			Metric::Euclidean => Err(anyhow!("the sum of euclidean distances is not an integer")),
+		}
+	}
+
+	/// Lists the distance between every pair of galaxies, in reading order of the first galaxy
+	/// and then the second.
+	fn pair_distances(&self, metric: Metric) -> Vec<(Galaxy, Galaxy, f64)> {
+		let galaxies = self.sorted_galaxies();
+		galaxies
+			.iter()
+			.enumerate()
+			.flat_map(|(i, &a)| {
+				galaxies[i + 1..]
+					.iter()
+					.map(move |&b| (a, b, metric.distance(a, b)))
+			})
+			.collect()
+	}
+
+	/// Returns the `k` galaxies closest to `galaxy`, closest first. Galaxies at the same distance
+	/// are ordered by position.
+	fn nearest(&self, galaxy: Galaxy, k: usize, metric: Metric) -> Vec<(Galaxy, f64)> {
+		let mut others = self
+			.galaxies
+			.iter()
+			.filter(|&&other| other != galaxy)
+			.map(|&other| (other, metric.distance(galaxy, other)))
+			.collect::<Vec<_>>();
+		let by_distance =
+			|a: &(Galaxy, f64), b: &(Galaxy, f64)| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0));
+		if k < others.len() {
+			others.select_nth_unstable_by(k, by_distance);
+			others.truncate(k);
+		}
+		others.sort_unstable_by(by_distance);
+		others
 	}
 
 	fn empty_rows_columns(&self) -> (HashSet<u64>, HashSet<u64>) {
@@ -49,37 +151,67 @@ impl Universe {
 		(empty_rows, empty_columns)
 	}
 
-	fn expand(&self, factor: u64) -> Universe {
-		let factor = factor - 1;
+	/// Expands every empty row to `row_factor` lines and every empty column to `column_factor`
+	/// lines
+	fn expand(&self, row_factor: u64, column_factor: u64) -> Universe {
+		self.expand_with(|_| row_factor, |_| column_factor)
+	}
+
+	/// Expands every empty row and column to the number of lines that `row_width` and
+	/// `column_width` return for its index. Widths below 1 count as 1.
+	fn expand_with(
+		&self,
+		row_width: impl Fn(u64) -> u64,
+		column_width: impl Fn(u64) -> u64,
+	) -> Universe {
 		let (empty_rows, empty_columns) = self.empty_rows_columns();
+		let rows_added = added_before(&empty_rows, self.height, row_width);
+		let columns_added = added_before(&empty_columns, self.width, column_width);
 		let expanded_galaxies = self
 			.galaxies
 			.iter()
-			.map(|(row, column)| {
+			.map(|&(row, column)| {
 				(
-					row + factor
-						* empty_rows
-							.iter()
-							.filter(|&empty_row| empty_row < row)
-							.count() as u64,
-					column
-						+ factor
-							* empty_columns
-								.iter()
-								.filter(|&empty_column| empty_column < column)
-								.count() as u64,
+					row + rows_added[row as usize],
+					column + columns_added[column as usize],
 				)
 			})
-			.collect::<HashSet<(u64, u64)>>();
+			.collect::<HashSet<Galaxy>>();
 
 		Universe {
 			galaxies: expanded_galaxies,
-			width: self.width + empty_columns.len() as u64 * factor,
-			height: self.height + empty_rows.len() as u64 * factor,
+			width: self.width + columns_added[self.width as usize],
+			height: self.height + rows_added[self.height as usize],
 		}
 	}
 }
 
+/// Prefix sums over `0..=size`, where entry `i` counts the lines that expanding the empty lines
+/// before line `i` adds.
+fn added_before(empty: &HashSet<u64>, size: u64, width: impl Fn(u64) -> u64) -> Vec<u64> {
+	let mut before = Vec::with_capacity(size as usize + 1);
+	before.push(0);
+	for line in 0..size {
+		let added = match empty.contains(&line) {
+			true => width(line).saturating_sub(1),
+			false => 0,
+		};
+		before.push(before[line as usize] + added);
+	}
+	before
+}
+
+fn axis_distance_sum(mut coordinates: Vec<u64>) -> u64 {
+	coordinates.sort_unstable();
+	let mut preceding_sum = 0;
+	let mut distance_sum = 0;
+	for (i, coordinate) in coordinates.into_iter().enumerate() {
+		distance_sum += coordinate * i as u64 - preceding_sum;
+		preceding_sum += coordinate;
+	}
+	distance_sum
+}
+
 fn parse_universe(lines: impl IntoIterator<Item = &'static str>) -> Universe {
 	let mut lines = lines.into_iter().peekable();
 
@@ -110,6 +242,14 @@ fn parse_universe(lines: impl IntoIterator<Item = &'static str>) -> Universe {
 mod tests {
 	use super::*;
 
+	fn solve(
+		lines: impl IntoIterator<Item = &'static str>,
+		expansion_factor: u64,
+	) -> anyhow::Result<u64> {
+		let universe = parse_universe(lines).expand(expansion_factor, expansion_factor);
+		universe.distance_sum(Metric::Manhattan)
+	}
+
 	const EXAMPLE_LINES: [&str; 10] = [
 		"...#......",
 		".......#..",
@@ -164,7 +304,7 @@ mod tests {
 		};
 
 		assert_eq!(
-			universe.expand(2),
+			universe.expand(2, 2),
 			Universe {
 				width: 13,
 				height: 12,
@@ -206,6 +346,135 @@ mod tests {
 		)
 	}
 
+	#[test]
+	fn test_added_before() {
+		assert_eq!(
+			added_before(&HashSet::from([2, 5, 8]), 10, |_| 2),
+			[0, 0, 0, 1, 1, 1, 2, 2, 2, 3, 3]
+		);
+		assert_eq!(
+			added_before(&HashSet::from([2, 5, 8]), 10, |line| line),
+			[0, 0, 0, 1, 1, 1, 5, 5, 5, 12, 12]
+		);
+		assert_eq!(added_before(&HashSet::new(), 0, |_| 2), [0]);
+	}
+
+	#[test]
+	fn test_expand_with() {
+		let universe = parse_universe(EXAMPLE_LINES);
+		// rows 3 and 7 are empty, as are columns 2, 5 and 8
+		let expanded = universe.expand_with(|_| 1, |_| 3);
+		assert_eq!((expanded.width, expanded.height), (16, 10));
+		assert!(expanded.galaxies.contains(&(9, 6)));
+
+		let expanded = universe.expand_with(|row| row, |_| 1);
+		assert_eq!((expanded.width, expanded.height), (10, 18));
+		assert!(expanded.galaxies.contains(&(9 + 2 + 6, 4)));
+	}
+
+	#[test]
+	fn test_metrics() {
+		assert_eq!(Metric::Manhattan.distance((0, 0), (3, 4)), 7.0);
+		assert_eq!(Metric::Chebyshev.distance((0, 0), (3, 4)), 4.0);
+		assert_eq!(Metric::Euclidean.distance((3, 4), (0, 0)), 5.0);
+		assert_eq!("Chebyshev".parse::<Metric>().unwrap(), Metric::Chebyshev);
+		assert!("taxicab".parse::<Metric>().is_err());
+	}
+
+	#[test]
+	fn test_distance_sum_metrics() {
+		let universe = parse_universe(EXAMPLE_LINES).expand(2, 2);
+		for metric in [Metric::Manhattan, Metric::Chebyshev] {
+			let pairwise = universe
+				.pair_distances(metric)
+				.into_iter()
+				.map(|(_, _, distance)| distance as u64)
+				.sum::<u64>();
+			assert_eq!(universe.distance_sum(metric).unwrap(), pairwise);
+		}
+		assert!(universe.distance_sum(Metric::Euclidean).is_err());
+	}
+
+	#[test]
+	fn test_pair_distances() {
+		let universe = parse_universe(EXAMPLE_LINES).expand(2, 2);
+		let pairs = universe.pair_distances(Metric::Manhattan);
+		assert_eq!(pairs.len(), 36);
+		// galaxies 5 and 9 in the puzzle's numbering
+		assert!(pairs.contains(&((6, 1), (11, 5), 9.0)));
+		assert_eq!(pairs[0], ((0, 4), (1, 9), 6.0));
+	}
+
+	#[test]
+	fn test_nearest() {
+		let universe = parse_universe(EXAMPLE_LINES).expand(2, 2);
+		assert_eq!(
+			universe.nearest((11, 0), 2, Metric::Manhattan),
+			[((11, 5), 5.0), ((6, 1), 6.0)]
+		);
+		assert_eq!(
+			universe.nearest((11, 0), 1, Metric::Euclidean),
+			[((11, 5), 5.0)]
+		);
+		assert_eq!(universe.nearest((11, 0), 100, Metric::Chebyshev).len(), 8);
+		assert_eq!(universe.nearest((11, 0), 0, Metric::Chebyshev), []);
+	}
+
+	#[test]
+	fn test_axis_distance_sum() {
+		assert_eq!(axis_distance_sum(vec![]), 0);
+		assert_eq!(axis_distance_sum(vec![4]), 0);
+		// |1 - 5| + |1 - 2| + |5 - 2| + 3 * 0 for the three equal coordinates
+		assert_eq!(axis_distance_sum(vec![5, 1, 2]), 8);
+		assert_eq!(axis_distance_sum(vec![3, 3, 3]), 0);
+	}
+
+	#[test]
+	fn test_distance_sum_many_galaxies() {
+		// 300,000 galaxies on a 1000x1000 grid, with every fourth row and every third column empty
+		let galaxies = (0..1000u64)
+			.filter(|row| row % 4 != 0)
+			.flat_map(|row| {
+				(0..1000u64)
+					.filter(|column| column % 3 != 0)
+					.map(move |column| (row, column))
+					.take(400)
+			})
+			.collect::<HashSet<_>>();
+		let universe = Universe {
+			galaxies,
+			width: 1000,
+			height: 1000,
+		};
+		assert_eq!(universe.galaxies.len(), 300_000);
+
+		let expanded = universe.expand(2, 2);
+		let brute_force_axis = |axis: Vec<u64>| {
+			let mut counts = std::collections::BTreeMap::<u64, u64>::new();
+			for coordinate in axis {
+				*counts.entry(coordinate).or_default() += 1;
+			}
+			let counts = counts.into_iter().collect::<Vec<_>>();
+			let mut sum = 0;
+			for (i, &(a, count_a)) in counts.iter().enumerate() {
+				for &(b, count_b) in &counts[i + 1..] {
+					sum += (b - a) * count_a * count_b;
+				}
+			}
+			sum
+		};
+		let rows = expanded.galaxies.iter().map(|&(row, _)| row).collect();
+		let columns = expanded
+			.galaxies
+			.iter()
+			.map(|&(_, column)| column)
+			.collect();
+		assert_eq!(
+			expanded.distance_sum(Metric::Manhattan).unwrap(),
+			brute_force_axis(rows) + brute_force_axis(columns)
+		);
+	}
+
 	mod part_1 {
 		use super::*;
 
@@ -221,5 +490,11 @@ mod tests {
 		fn test_example() {
 			assert_eq!(solve(EXAMPLE_LINES, 1000000).unwrap(), 82000210);
 		}
+
+		#[test]
+		fn test_smaller_factors() {
+			assert_eq!(solve(EXAMPLE_LINES, 10).unwrap(), 1030);
+			assert_eq!(solve(EXAMPLE_LINES, 100).unwrap(), 8410);
+		}
 	}
 }
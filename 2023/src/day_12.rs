@@ -1,22 +1,173 @@
-use cached::proc_macro::cached;
+use std::fmt::Display;
+
+use anyhow::Context;
 use rayon::prelude::*;
 
 const INPUT: &'static str = include_str!("../../inputs/2023/day_12.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u64> {
-	solve(INPUT.par_lines(), part_two)
+	let unfolding = match part_two {
+		false => Unfolding::NONE,
+		true => Unfolding::prompt()?,
+	};
+	if let Some(line) = crate::option("Print arrangements of the record on line (none)")? {
+		print_arrangements(line, &unfolding)?;
+	}
+	if !part_two {
+		return solve(INPUT.par_lines(), &unfolding);
+	}
+
+	let max_factor = crate::option::<usize>("Enter a factor to report the growth up to (none)")?;
+	if let Some(max_factor) = max_factor {
+		for line in growth_report(INPUT.par_lines(), max_factor, unfolding.separator)? {
+			println!("{}", line);
+		}
+	}
+
+	solve(INPUT.par_lines(), &unfolding)
 }
 
 fn solve(
 	records: impl IntoParallelIterator<Item = &'static str>,
-	unfold: bool,
+	unfolding: &Unfolding,
 ) -> anyhow::Result<u64> {
 	records
 		.into_par_iter()
-		.map(|line| Record::try_from(&line[..]))
-		.map(|record| anyhow::Ok(if unfold { record?.unfold() } else { record? }))
-		.map(|record| anyhow::Ok(record?.count_arrangements()))
-		.sum()
+		.map(|line| {
+			let record = Record::try_from(line)?;
+			record
+				.unfold(unfolding)
+				.count_arrangements()
+				.with_context(|| format!("unfolding {} {} times", record, unfolding.factor))
+		})
+		.try_reduce(
+			|| 0,
+			|a, b| {
+				a.checked_add(b)
+					.context("the sum of all arrangements does not fit a u64")
+			},
+		)
+}
+
+/// Arrangements to print when not asked for a specific one, since unfolded records can have
+/// billions of them
+const ARRANGEMENTS_SAMPLE: usize = 10;
+
+/// Prints the first few arrangements of the record on the 1-based `line`, or a chosen one of them
+fn print_arrangements(line: usize, unfolding: &Unfolding) -> anyhow::Result<()> {
+	let record = line
+		.checked_sub(1)
+		.and_then(|i| INPUT.lines().nth(i))
+		.ok_or(anyhow::anyhow!("there is no record on line {}", line))?;
+	let record = Record::try_from(record)?.unfold(unfolding);
+	let arrangements = record.arrangements()?;
+	let format =
+		|arrangement: Vec<Condition>| arrangement.into_iter().map(char::from).collect::<String>();
+
+	println!("{}: {} arrangements", record, arrangements.count());
+	let message = format!("Arrangement to show, starting at 0 (first {ARRANGEMENTS_SAMPLE})");
+	match crate::option::<u64>(&message)? {
+		Some(n) => match arrangements.nth(n) {
+			Some(arrangement) => println!("{}", format(arrangement)),
+			None => println!("there are only {} arrangements", arrangements.count()),
+		},
+		None => {
+			for arrangement in arrangements.iter().take(ARRANGEMENTS_SAMPLE) {
+				println!("{}", format(arrangement));
+			}
+			if arrangements.count() > ARRANGEMENTS_SAMPLE as u64 {
+				println!("...");
+			}
+		}
+	}
+	Ok(())
+}
+
+/// Describes for every record how its amount of arrangements grows when unfolding it 1 to
+/// `max_factor` times
+fn growth_report(
+	records: impl IntoParallelIterator<Item = &'static str>,
+	max_factor: usize,
+	separator: Condition,
+) -> anyhow::Result<Vec<String>> {
+	records
+		.into_par_iter()
+		.map(|line| {
+			let record = Record::try_from(line)?;
+			let growth = record.growth(max_factor, separator)?;
+			let counts = growth.iter().map(u64::to_string).collect::<Vec<_>>();
+			let progression = match geometric_ratio(&growth) {
+				Some((numerator, 1)) => format!("geometric with ratio {}", numerator),
+				Some((numerator, denominator)) => {
+					format!("geometric with ratio {}/{}", numerator, denominator)
+				}
+				None => "not geometric".to_string(),
+			};
+			Ok(format!(
+				"{}: {} ({})",
+				record,
+				counts.join(", "),
+				progression
+			))
+		})
+		.collect()
+}
+
+/// Returns the reduced ratio between consecutive counts if there is a common one
+fn geometric_ratio(counts: &[u64]) -> Option<(u64, u64)> {
+	let (&first, &second) = (counts.first()?, counts.get(1)?);
+	let is_geometric = counts.windows(3).all(|window| {
+		let [previous, current, next] = [window[0], window[1], window[2]].map(u128::from);
+		current * current == previous * next
+	});
+	if first == 0 || !is_geometric {
+		return None;
+	}
+	let divisor = gcd(second, first);
+	Some((second / divisor, first / divisor))
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+	match b {
+		0 => a,
+		_ => gcd(b, a % b),
+	}
+}
+
+/// How a record is unfolded: `factor` copies of its conditions, joined by `separator`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Unfolding {
+	factor: usize,
+	separator: Condition,
+}
+
+impl Unfolding {
+	/// Leaves the record as is
+	const NONE: Unfolding = Unfolding {
+		factor: 1,
+		separator: U,
+	};
+
+	fn prompt() -> anyhow::Result<Self> {
+		let default = Unfolding::default();
+		let factor =
+			crate::option::<usize>("Enter an unfold factor (5)")?.unwrap_or(default.factor);
+		anyhow::ensure!(factor > 0, "unfold factor must be at least 1");
+		let separator = crate::option::<char>("Enter an unfold separator (?)")?
+			.map(Condition::try_from)
+			.transpose()?
+			.unwrap_or(default.separator);
+		Ok(Unfolding { factor, separator })
+	}
+}
+
+impl Default for Unfolding {
+	fn default() -> Self {
+		Unfolding {
+			factor: 5,
+			separator: U,
+		}
+	}
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -30,6 +181,16 @@ const O: Condition = Condition::Operational;
 const D: Condition = Condition::Damaged;
 const U: Condition = Condition::Unknown;
 
+impl From<Condition> for char {
+	fn from(value: Condition) -> Self {
+		match value {
+			O => '.',
+			D => '#',
+			U => '?',
+		}
+	}
+}
+
 impl TryFrom<char> for Condition {
 	type Error = anyhow::Error;
 
@@ -53,116 +214,189 @@ struct Record {
 }
 
 impl Record {
-	fn unfold(&self) -> Self {
+	fn unfold(&self, unfolding: &Unfolding) -> Self {
 		Record {
-			conditions: vec![self.conditions.clone(); 5].join(&U),
-			groups: self.groups.repeat(5),
-		}
-	}
-
-	fn count_arrangements(&self) -> u64 {
-		let result = match self.conditions.first().copied() {
-			Some(U) => {
-				let as_operational = Checkpoint::new(
-					O,
-					self.conditions[1..].to_vec(),
-					self.groups.first().unwrap_or(&0).to_owned(),
-					self.groups[1..].to_vec(),
-				);
-				let as_damaged = Checkpoint::new(
-					D,
-					self.conditions[1..].to_vec(),
-					self.groups.first().unwrap_or(&0).to_owned(),
-					self.groups[1..].to_vec(),
-				);
-				count_arrangements(as_operational) + count_arrangements(as_damaged)
-			}
-			Some(first_condition @ (O | D)) => count_arrangements(Checkpoint::new(
-				first_condition,
-				self.conditions[1..].to_vec(),
-				self.groups.first().unwrap_or(&0).to_owned(),
-				self.groups[1..].to_vec(),
-			)),
-			None => 0,
-		};
-		result
+			conditions: vec![self.conditions.clone(); unfolding.factor].join(&unfolding.separator),
+			groups: self.groups.repeat(unfolding.factor),
+		}
 	}
-}
 
-#[derive(Debug, PartialEq, Eq, Clone, Hash)]
-struct Checkpoint {
-	condition: Condition, // TODO: `Unknown` is not allowed here
-	rest_conditions: Vec<Condition>,
-	group: u64,
-	rest_groups: Vec<u64>,
-}
+	/// Amount of arrangements when unfolding the record 1 to `max_factor` times
+	fn growth(&self, max_factor: usize, separator: Condition) -> anyhow::Result<Vec<u64>> {
+		(1..=max_factor)
+			.map(|factor| {
+				let unfolding = Unfolding { factor, separator };
+				self.unfold(&unfolding)
+					.count_arrangements()
+					.with_context(|| format!("unfolding {} {} times", self, factor))
+			})
+			.collect()
+	}
+
+	/// Counts the arrangements with only two columns of the bottom-up table in memory, one for
+	/// the groups from `j` onwards and one for those from `j + 1` onwards.
+	fn count_arrangements(&self) -> anyhow::Result<u64> {
+		let length = self.conditions.len();
+		let damaged_run = self.damaged_run();
+		let mut next_ways = vec![0u64; length + 1];
+		self.fill_last_ways(&mut next_ways);
+
+		let mut ways = vec![0u64; length + 1];
+		for &group in self.groups.iter().rev() {
+			self.fill_ways(&damaged_run, group, &next_ways, &mut ways)?;
+			std::mem::swap(&mut ways, &mut next_ways);
+		}
+
+		Ok(next_ways[0])
+	}
+
+	fn arrangements(&self) -> anyhow::Result<Arrangements<'_>> {
+		Arrangements::new(self)
+	}
+
+	/// Amount of conditions from `i` onwards that could be damaged without interruption
+	fn damaged_run(&self) -> Vec<usize> {
+		let conditions = &self.conditions;
+		let mut damaged_run = vec![0; conditions.len() + 1];
+		for i in (0..conditions.len()).rev() {
+			damaged_run[i] = match conditions[i] {
+				O => 0,
+				D | U => damaged_run[i + 1] + 1,
+			};
+		}
+		damaged_run
+	}
+
+	/// Fills in the arrangements of the conditions from `i` onwards into no groups, of which
+	/// there is one as long as nothing is damaged
+	fn fill_last_ways(&self, ways: &mut [u64]) {
+		let conditions = &self.conditions;
+		ways[conditions.len()] = 1;
+		for i in (0..conditions.len()).rev() {
+			ways[i] = match conditions[i] {
+				D => 0,
+				O | U => ways[i + 1],
+			};
+		}
+	}
 
-impl Checkpoint {
-	fn new(
-		condition: Condition,
-		rest_conditions: Vec<Condition>,
+	/// Fills in the arrangements of the conditions from `i` onwards into `group` and the groups
+	/// after it, whose arrangements are `next_ways`. Fails if they do not fit a `u64`.
+	fn fill_ways(
+		&self,
+		damaged_run: &[usize],
 		group: u64,
-		rest_groups: Vec<u64>,
-	) -> Self {
-		Checkpoint {
-			condition,
-			rest_conditions,
-			group,
-			rest_groups,
+		next_ways: &[u64],
+		ways: &mut [u64],
+	) -> anyhow::Result<()> {
+		let conditions = &self.conditions;
+		let length = conditions.len();
+		let group = group as usize;
+		ways[length] = 0;
+		for i in (0..length).rev() {
+			let as_operational = match conditions[i] {
+				D => 0,
+				O | U => ways[i + 1],
+			};
+			let fits_group = damaged_run[i] >= group
+				&& conditions
+					.get(i + group)
+					.is_none_or(|&condition| condition != D);
+			let as_damaged = match (conditions[i], fits_group) {
+				(D | U, true) => next_ways[(i + group + 1).min(length)],
+				_ => 0,
+			};
+			ways[i] = as_operational
+				.checked_add(as_damaged)
+				.with_context(|| format!("{} has more arrangements than fit a u64", self))?;
 		}
+		Ok(())
 	}
 }
 
-#[cached]
-fn count_arrangements(checkpoint: Checkpoint) -> u64 {
-	let mut is_after_damaged = false;
-	let mut next_condition = Some(checkpoint.condition);
-	let mut current_group = checkpoint.group;
-	let mut rest_groups = checkpoint.rest_groups;
-	let mut rest_conditions = checkpoint.rest_conditions;
-
-	while let Some(current_condition) = next_condition {
-		match (is_after_damaged, current_condition, current_group) {
-			(false, O | U, 0) => (),
-			(false, O, 1..) => (),
-			(true, O | U, 0) => {
-				let next_group = rest_groups.first().copied();
-				if next_group.is_some() {
-					rest_groups = rest_groups[1..].to_vec();
-				}
-				current_group = next_group.unwrap_or(0);
-				is_after_damaged = false;
-			}
-			(true, O, 1..) => return 0,
-			(false, D, 0) => return 0,
-			(false, D, 1..) => {
-				current_group -= 1;
-				is_after_damaged = true;
+/// Every way to resolve the unknown conditions of a record, ordered lexicographically where an
+/// operational spring comes before a damaged one.
+///
+/// Unlike [`Record::count_arrangements`], this keeps the whole bottom-up table, where
+/// `ways[j * (length + 1) + i]` is the amount of arrangements of the conditions from `i` onwards
+/// into the groups from `j` onwards.
+struct Arrangements<'r> {
+	record: &'r Record,
+	ways: Vec<u64>,
+}
+
+impl<'r> Arrangements<'r> {
+	/// Fails if there are more arrangements than fit a `u64`
+	fn new(record: &'r Record) -> anyhow::Result<Self> {
+		let length = record.conditions.len();
+		let damaged_run = record.damaged_run();
+		let mut ways = vec![0u64; (record.groups.len() + 1) * (length + 1)];
+		let (previous_ways, last_ways) = ways.split_at_mut(record.groups.len() * (length + 1));
+		record.fill_last_ways(last_ways);
+
+		let mut next_ways = &*last_ways;
+		let columns = previous_ways.chunks_exact_mut(length + 1);
+		for (ways, &group) in columns.rev().zip(record.groups.iter().rev()) {
+			record.fill_ways(&damaged_run, group, next_ways, ways)?;
+			next_ways = ways;
+		}
+
+		Ok(Arrangements { record, ways })
+	}
+
+	fn ways(&self, condition_index: usize, group_index: usize) -> u64 {
+		self.ways[group_index * (self.record.conditions.len() + 1) + condition_index]
+	}
+
+	fn count(&self) -> u64 {
+		self.ways(0, 0)
+	}
+
+	/// Builds the `n`-th arrangement directly, by skipping every branch with fewer arrangements
+	/// than are left to skip.
+	fn nth(&self, mut n: u64) -> Option<Vec<Condition>> {
+		if n >= self.count() {
+			return None;
+		}
+
+		let conditions = &self.record.conditions;
+		let mut arrangement = Vec::with_capacity(conditions.len());
+		let mut group_index = 0;
+		while arrangement.len() < conditions.len() {
+			let i = arrangement.len();
+			let as_operational = match conditions[i] {
+				D => 0,
+				O | U => self.ways(i + 1, group_index),
+			};
+			if n < as_operational {
+				arrangement.push(O);
+				continue;
 			}
-			(true, D, 0) => return 0,
-			(true, D | U, 1..) => current_group -= 1,
-			(false, U, 1..) => {
-				let as_operational = Checkpoint::new(
-					O,
-					rest_conditions.clone(),
-					current_group,
-					rest_groups.clone(),
-				);
-				let as_damaged = Checkpoint::new(D, rest_conditions, current_group, rest_groups);
-				return count_arrangements(as_operational) + count_arrangements(as_damaged);
+
+			n -= as_operational;
+			let group = self.record.groups[group_index] as usize;
+			arrangement.extend(std::iter::repeat_n(D, group));
+			if arrangement.len() < conditions.len() {
+				arrangement.push(O);
 			}
+			group_index += 1;
 		}
-		next_condition = rest_conditions.first().copied();
-		if next_condition.is_some() {
-			rest_conditions = rest_conditions[1..].to_vec();
-		}
+
+		Some(arrangement)
 	}
 
-	if current_group == 0 && rest_groups.is_empty() {
-		return 1;
+	fn iter(&self) -> impl Iterator<Item = Vec<Condition>> + '_ {
+		// this cannot panic because `n` is less than the amount of arrangements
+		(0..self.count()).map(|n| self.nth(n).unwrap())
 	}
+}
 
-	0
+impl Display for Record {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let conditions = self.conditions.iter().copied().map(char::from);
+		let groups = self.groups.iter().map(u64::to_string).collect::<Vec<_>>();
+		write!(f, "{} {}", conditions.collect::<String>(), groups.join(","))
+	}
 }
 
 impl TryFrom<&str> for Record {
@@ -184,6 +418,10 @@ impl TryFrom<&str> for Record {
 			.map(|group| group.parse::<u64>())
 			.collect::<Result<Vec<_>, _>>()?;
 
+		if groups.contains(&0) {
+			return Err(anyhow::anyhow!("groups must not be empty, got {}", value));
+		}
+
 		Ok(Record { conditions, groups })
 	}
 }
@@ -234,6 +472,215 @@ mod tests {
 		}
 	}
 
+	/// Tries every combination of unknown conditions
+	fn count_arrangements_brute_force(record: &Record) -> u64 {
+		let unknowns = record.conditions.iter().filter(|&&c| c == U).count();
+		(0..1u64 << unknowns)
+			.filter(|combination| {
+				let mut bits = (0..unknowns).map(|bit| combination & 1 << bit != 0);
+				let groups = record
+					.conditions
+					.iter()
+					.map(|&condition| match condition {
+						U if bits.next().unwrap() => D,
+						U => O,
+						condition => condition,
+					})
+					.collect::<Vec<_>>()
+					.split(|&condition| condition == O)
+					.map(|group| group.len() as u64)
+					.filter(|&length| length > 0)
+					.collect::<Vec<_>>();
+				groups == record.groups
+			})
+			.count() as u64
+	}
+
+	#[test]
+	fn test_count_arrangements_brute_force() {
+		let records = [
+			example_1(),
+			example_2(),
+			example_3(),
+			example_4(),
+			example_5(),
+			example_6(),
+			Record::try_from("?#?.??#??#?? 1,2,3").unwrap(),
+			Record::try_from("??????? 2,1").unwrap(),
+			Record::try_from("#?#?#? 6").unwrap(),
+			Record::try_from("??.## 3").unwrap(),
+			Record::try_from(".... 1").unwrap(),
+		];
+		for record in records {
+			assert_eq!(
+				record.count_arrangements().unwrap(),
+				count_arrangements_brute_force(&record),
+				"{:?}",
+				record
+			);
+		}
+	}
+
+	fn render(arrangement: Vec<Condition>) -> String {
+		arrangement.into_iter().map(char::from).collect()
+	}
+
+	#[test]
+	fn test_arrangements() {
+		let record = Record::try_from("?###???????? 3,2,1").unwrap();
+		let arrangements = record.arrangements().unwrap();
+		let rendered = arrangements.iter().map(render).collect::<Vec<_>>();
+		assert_eq!(
+			rendered,
+			vec![
+				".###....##.#",
+				".###...##..#",
+				".###...##.#.",
+				".###..##...#",
+				".###..##..#.",
+				".###..##.#..",
+				".###.##....#",
+				".###.##...#.",
+				".###.##..#..",
+				".###.##.#...",
+			]
+		);
+		assert_eq!(
+			arrangements.nth(4).map(render),
+			Some(".###..##..#.".to_string())
+		);
+		assert_eq!(arrangements.nth(10), None);
+	}
+
+	#[test]
+	fn test_arrangements_brute_force() {
+		let records = [
+			example_2(),
+			example_4(),
+			example_5(),
+			Record::try_from("?#?.??#??#?? 1,2,3").unwrap(),
+			Record::try_from("??????? 2,1").unwrap(),
+		];
+		for record in records {
+			let arrangements = record.arrangements().unwrap().iter().collect::<Vec<_>>();
+			assert_eq!(
+				arrangements.len() as u64,
+				count_arrangements_brute_force(&record)
+			);
+			for arrangement in &arrangements {
+				let resolved = Record {
+					conditions: arrangement.clone(),
+					groups: record.groups.clone(),
+				};
+				assert_eq!(resolved.count_arrangements().unwrap(), 1);
+				assert!(record
+					.conditions
+					.iter()
+					.zip(arrangement)
+					.all(|(&condition, &resolved)| condition == U || condition == resolved));
+			}
+			let rendered = arrangements.into_iter().map(render).collect::<Vec<_>>();
+			assert!(rendered.windows(2).all(|pair| {
+				// an operational spring comes before a damaged one, just like '.' before '#'
+				let key = |s: &str| s.replace('.', "0").replace('#', "1");
+				key(&pair[0]) < key(&pair[1])
+			}));
+		}
+	}
+
+	#[test]
+	fn test_nth_arrangement_unfolded() {
+		let record = example_6().unfold(&Unfolding::default());
+		let arrangements = record.arrangements().unwrap();
+		assert_eq!(arrangements.count(), 506250);
+		let last = arrangements.nth(506249).unwrap();
+		let resolved = Record {
+			conditions: last,
+			groups: record.groups.clone(),
+		};
+		assert_eq!(resolved.count_arrangements().unwrap(), 1);
+	}
+
+	#[test]
+	fn test_unfold() {
+		let record = Record::try_from(".# 1").unwrap();
+		let unfolding = Unfolding {
+			factor: 3,
+			separator: O,
+		};
+		assert_eq!(
+			record.unfold(&unfolding),
+			Record::try_from(".#..#..# 1,1,1").unwrap()
+		);
+		assert_eq!(record.unfold(&Unfolding::NONE), record);
+	}
+
+	#[test]
+	fn test_display() {
+		let record = "?#?.??#??#?? 1,2,3";
+		assert_eq!(Record::try_from(record).unwrap().to_string(), record);
+	}
+
+	#[test]
+	fn test_growth() {
+		assert_eq!(
+			example_2().growth(5, U).unwrap(),
+			vec![4, 32, 256, 2048, 16384]
+		);
+		assert_eq!(example_2().growth(3, O).unwrap(), vec![4, 16, 64]);
+		assert_eq!(example_6().growth(3, U).unwrap(), vec![10, 150, 2250]);
+	}
+
+	#[test]
+	fn test_overflow() {
+		// 10 * 15^16 arrangements, which is more than `u64::MAX`
+		let unfolding = Unfolding {
+			factor: 17,
+			separator: U,
+		};
+		assert!(example_6().unfold(&unfolding).count_arrangements().is_err());
+		assert!(example_6().unfold(&unfolding).arrangements().is_err());
+		assert!(example_6().growth(17, U).is_err());
+		assert_eq!(example_6().growth(16, U).unwrap()[15], 10 * 15u64.pow(15));
+
+		// every record fits on its own, but their sum does not
+		let unfolding = Unfolding {
+			factor: 16,
+			separator: U,
+		};
+		let records = ["?###???????? 3,2,1"; 5];
+		assert!(solve(records, &unfolding).is_err());
+		assert_eq!(
+			solve(["?###???????? 3,2,1"; 4], &unfolding).unwrap(),
+			40 * 15u64.pow(15)
+		);
+	}
+
+	#[test]
+	fn test_geometric_ratio() {
+		assert_eq!(geometric_ratio(&[4, 32, 256, 2048, 16384]), Some((8, 1)));
+		assert_eq!(geometric_ratio(&[4, 6, 9]), Some((3, 2)));
+		assert_eq!(geometric_ratio(&[1, 2, 5]), None);
+		assert_eq!(geometric_ratio(&[0, 0, 0]), None);
+	}
+
+	#[test]
+	fn test_growth_report() {
+		let records = ["???.### 1,1,3", "?###???????? 3,2,1"];
+		assert_eq!(
+			growth_report(records, 3, U).unwrap(),
+			vec![
+				"???.### 1,1,3: 1, 1, 1 (geometric with ratio 1)",
+				"?###???????? 3,2,1: 10, 150, 2250 (geometric with ratio 15)",
+			]
+		);
+	}
+
+	#[test]
+	fn test_parse_empty_group() {
+		assert!(Record::try_from("??? 0,1").is_err());
+	}
+
 	mod part_1 {
 		use super::*;
 
@@ -245,7 +692,7 @@ mod tests {
 
 		#[test]
 		fn test_example_1_count_arrangements() {
-			assert_eq!(example_1().count_arrangements(), 1);
+			assert_eq!(example_1().count_arrangements().unwrap(), 1);
 		}
 
 		#[test]
@@ -256,7 +703,7 @@ mod tests {
 
 		#[test]
 		fn test_example_2_count_arrangements() {
-			assert_eq!(example_2().count_arrangements(), 4);
+			assert_eq!(example_2().count_arrangements().unwrap(), 4);
 		}
 
 		#[test]
@@ -267,7 +714,7 @@ mod tests {
 
 		#[test]
 		fn test_example_3_count_arrangements() {
-			assert_eq!(example_3().count_arrangements(), 1);
+			assert_eq!(example_3().count_arrangements().unwrap(), 1);
 		}
 
 		#[test]
@@ -278,7 +725,7 @@ mod tests {
 
 		#[test]
 		fn test_example_4_count_arrangements() {
-			assert_eq!(example_4().count_arrangements(), 1);
+			assert_eq!(example_4().count_arrangements().unwrap(), 1);
 		}
 
 		#[test]
@@ -289,7 +736,7 @@ mod tests {
 
 		#[test]
 		fn test_example_5_count_arrangements() {
-			assert_eq!(example_5().count_arrangements(), 4);
+			assert_eq!(example_5().count_arrangements().unwrap(), 4);
 		}
 
 		#[test]
@@ -300,7 +747,7 @@ mod tests {
 
 		#[test]
 		fn test_example_6_count_arrangements() {
-			assert_eq!(example_6().count_arrangements(), 10);
+			assert_eq!(example_6().count_arrangements().unwrap(), 10);
 		}
 	}
 
@@ -309,32 +756,68 @@ mod tests {
 
 		#[test]
 		fn test_example_1_count_arrangements() {
-			assert_eq!(example_1().unfold().count_arrangements(), 1);
+			assert_eq!(
+				example_1()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				1
+			);
 		}
 
 		#[test]
 		fn test_example_2_count_arrangements() {
-			assert_eq!(example_2().unfold().count_arrangements(), 16384);
+			assert_eq!(
+				example_2()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				16384
+			);
 		}
 
 		#[test]
 		fn test_example_3_count_arrangements() {
-			assert_eq!(example_3().unfold().count_arrangements(), 1);
+			assert_eq!(
+				example_3()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				1
+			);
 		}
 
 		#[test]
 		fn test_example_4_count_arrangements() {
-			assert_eq!(example_4().unfold().count_arrangements(), 16);
+			assert_eq!(
+				example_4()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				16
+			);
 		}
 
 		#[test]
 		fn test_example_5_count_arrangements() {
-			assert_eq!(example_5().unfold().count_arrangements(), 2500);
+			assert_eq!(
+				example_5()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				2500
+			);
 		}
 
 		#[test]
 		fn test_example_6_count_arrangements() {
-			assert_eq!(example_6().unfold().count_arrangements(), 506250);
+			assert_eq!(
+				example_6()
+					.unfold(&Unfolding::default())
+					.count_arrangements()
+					.unwrap(),
+				506250
+			);
 		}
 	}
 }
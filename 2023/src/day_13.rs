@@ -1,93 +1,155 @@
 use std::ops::{Deref, DerefMut};
 
-use anyhow::anyhow;
-
 const INPUT: &'static str = include_str!("../../inputs/2023/day_13.txt");
 
 pub fn main(part_two: bool) -> anyhow::Result<u32> {
-	if part_two {
-		todo!();
+	let smudges = if part_two { 1 } else { 0 };
+	let summary = solve(INPUT.lines(), smudges)?;
+
+	if crate::option("Print reflections? (false)")?.unwrap_or(false) {
+		let regions = Region::parse_many(INPUT.lines())?;
+		for (i, region) in regions.iter().enumerate() {
+			println!(
+				"Region {i}:\n{}\n",
+				region.draw(&region.find_reflections(smudges))
+			);
+		}
 	}
-	solve_part_1(INPUT.lines())
+	for warning in summary.warnings() {
+		println!("{warning}");
+	}
+
+	Ok(summary.score)
 }
 
-fn solve_part_1(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u32> {
+fn solve(lines: impl IntoIterator<Item = &'static str>, smudges: u32) -> anyhow::Result<Summary> {
 	let regions = Region::parse_many(lines)?;
-	regions
-		.into_iter()
-		.map(|region| region.find_reflection().ok_or(anyhow!("no reflection")))
-		.map(|reflection| Ok(reflection?.score()?))
-		.sum()
+	let mut summary = Summary::default();
+	for (i, region) in regions.iter().enumerate() {
+		let reflections = region.find_reflections(smudges);
+		match reflections.as_slice() {
+			[] => summary.without_reflection.push(i),
+			[reflection] => summary.score += reflection.score()?,
+			[reflection, ..] => {
+				summary.score += reflection.score()?;
+				summary.multiple_reflections.push((i, reflections.len()));
+			}
+		}
+	}
+	Ok(summary)
+}
+
+/// The summed score of all regions, along with the regions that do not have exactly one
+/// reflection
+#[derive(Debug, Default, PartialEq)]
+struct Summary {
+	score: u32,
+	/// Indices of the regions without any reflection, which do not count towards the score
+	without_reflection: Vec<usize>,
+	/// Indices of the regions with more than one reflection, and how many they have. Only their
+	/// first reflection counts towards the score.
+	multiple_reflections: Vec<(usize, usize)>,
 }
 
+impl Summary {
+	fn warnings(&self) -> Vec<String> {
+		let without = self
+			.without_reflection
+			.iter()
+			.map(|i| format!("region {i} has no reflection"));
+		let multiple = self.multiple_reflections.iter().map(|(i, count)| {
+			format!("region {i} has {count} reflections, only the first one is scored")
+		});
+		without.chain(multiple).collect()
+	}
+}
+
+/// Rows and columns are stored as bitmasks, so a region can be at most this wide and high
+const MAX_REGION_SIZE: usize = u64::BITS as usize;
+
 #[derive(Clone, Debug, PartialEq)]
 struct Region(Vec<Vec<Terrain>>);
 
 impl Region {
-	fn find_reflection(mut self) -> Option<Reflection> {
-		if let Some(reflection_before_index) = self.find_reflection_normalized() {
-			return Some(Reflection {
+	/// Finds every reflection whose mirrored tiles differ in exactly `smudges` tiles.
+	/// Horizontal reflections come before vertical ones.
+	fn find_reflections(&self, smudges: u32) -> Vec<Reflection> {
+		let horizontal = find_reflection_axes(&self.row_masks(), smudges)
+			.into_iter()
+			.map(|before_index| Reflection {
 				axis: Axis::Horizontal,
-				before_index: reflection_before_index,
+				before_index,
 			});
-		}
-
-		self.reverse();
-
-		if let Some(reflection_before_index) = self.find_reflection_normalized() {
-			return Some(Reflection {
-				axis: Axis::Horizontal,
-				before_index: self.len() - reflection_before_index,
-			});
-		}
-
-		let mut transposed = self.transpose();
-
-		if let Some(reflection_before_index) = transposed.find_reflection_normalized() {
-			return Some(Reflection {
+		let vertical = find_reflection_axes(&self.column_masks(), smudges)
+			.into_iter()
+			.map(|before_index| Reflection {
 				axis: Axis::Vertical,
-				before_index: reflection_before_index,
+				before_index,
 			});
-		}
-
-		transposed.reverse();
+		horizontal.chain(vertical).collect()
+	}
 
-		if let Some(reflection_before_index) = transposed.find_reflection_normalized() {
-			return Some(Reflection {
-				axis: Axis::Vertical,
-				before_index: transposed.len() - reflection_before_index,
-			});
+	/// Draws the region with a `-` line between the rows and a `|` line between the columns of
+	/// every reflection
+	fn draw(&self, reflections: &[Reflection]) -> String {
+		let axes = |axis: Axis| {
+			reflections
+				.iter()
+				.filter(move |reflection| reflection.axis == axis)
+				.map(|reflection| reflection.before_index)
+				.collect::<Vec<_>>()
+		};
+		let (rows, columns) = (axes(Axis::Horizontal), axes(Axis::Vertical));
+
+		let draw_row = |tiles: Vec<char>, separator: char| {
+			let mut line = String::new();
+			for (column, tile) in tiles.into_iter().enumerate() {
+				if columns.contains(&column) {
+					line.push(separator);
+				}
+				line.push(tile);
+			}
+			line
+		};
+		let width = self.first().map_or(0, Vec::len);
+
+		let mut lines = Vec::new();
+		for (row, terrain) in self.iter().enumerate() {
+			if rows.contains(&row) {
+				lines.push(draw_row(vec!['-'; width], '+'));
+			}
+			lines.push(draw_row(
+				terrain.iter().map(|&tile| tile.into()).collect(),
+				'|',
+			));
 		}
-
-		None
+		lines.join("\n")
 	}
 
-	fn find_reflection_normalized(&self) -> Option<usize> {
-		let first_row = self.first()?;
-		let mut first_row_matches = self
-			.iter()
-			.enumerate()
-			.filter(|(i, _)| i % 2 == 1) // row matches from reflections can only occur on odd rows
-			.filter(|(_, row)| row == &first_row)
-			.map(|(i, _)| i);
-
-		let reflection_end = first_row_matches.find(|&i| {
-			let (half, mirrored_half) = self[1..i].split_at(i / 2);
-			return half.iter().rev().zip(mirrored_half).all(|(a, b)| a == b);
-		})?;
-
-		Some(reflection_end / 2 + 1)
+	fn row_masks(&self) -> Vec<u64> {
+		self.iter()
+			.map(|row| {
+				row.iter()
+					.fold(0, |mask, &terrain| mask << 1 | terrain.bit())
+			})
+			.collect()
 	}
 
-	fn new() -> Self {
-		Self(Vec::new())
+	fn column_masks(&self) -> Vec<u64> {
+		let columns = self.first().map_or(0, Vec::len);
+		(0..columns)
+			.map(|column| {
+				self.iter()
+					.fold(0, |mask, row| mask << 1 | row[column].bit())
+			})
+			.collect()
 	}
 
 	fn parse_many(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<Vec<Self>> {
-		lines.into_iter().map(Self::parse_partial).try_fold(
+		let regions = lines.into_iter().map(Self::parse_partial).try_fold(
 			vec![Region(Vec::new())],
 			|acc: Vec<Region>, region_row| {
-				Ok(match region_row? {
+				anyhow::Ok(match region_row? {
 					Some(region_row) => {
 						let (last_region, regions) = acc.split_last().unwrap();
 						let mut last_region = Region(last_region.to_vec());
@@ -97,7 +159,25 @@ impl Region {
 					None => [acc.as_slice(), &[Region(Vec::new())]].concat(),
 				})
 			},
-		)
+		)?;
+
+		for (i, region) in regions.iter().enumerate() {
+			let width = region.first().map_or(0, Vec::len);
+			anyhow::ensure!(
+				region.iter().all(|row| row.len() == width),
+				"region {} is not rectangular",
+				i
+			);
+			anyhow::ensure!(
+				width <= MAX_REGION_SIZE && region.len() <= MAX_REGION_SIZE,
+				"region {} is larger than {}x{}",
+				i,
+				MAX_REGION_SIZE,
+				MAX_REGION_SIZE
+			);
+		}
+
+		Ok(regions)
 	}
 
 	fn parse_partial(line: &str) -> anyhow::Result<Option<Vec<Terrain>>> {
@@ -113,22 +193,19 @@ impl Region {
 
 		Ok(partial_region)
 	}
+}
 
-	fn transpose(self) -> Region {
-		let Some(first_row) = self.first() else {
-			return Region::new();
-		};
-		let columns = first_row.len();
-		let mut partial_regions: Vec<_> = self.0.into_iter().map(Vec::into_iter).collect();
-		(0..columns)
-			.map(|_| {
-				partial_regions
-					.iter_mut()
-					.map(|partial_region| partial_region.next().unwrap())
-					.collect()
-			})
-			.collect()
-	}
+/// Returns the amount of lines before every axis at which the mirrored lines differ in exactly
+/// `smudges` bits
+fn find_reflection_axes(masks: &[u64], smudges: u32) -> Vec<usize> {
+	(1..masks.len())
+		.filter(|&before_index| {
+			let before = masks[..before_index].iter().rev();
+			let after = &masks[before_index..];
+			let differences = before.zip(after).map(|(a, b)| (a ^ b).count_ones());
+			differences.sum::<u32>() == smudges
+		})
+		.collect()
 }
 
 impl Deref for Region {
@@ -157,7 +234,7 @@ impl FromIterator<Vec<Terrain>> for Region {
 	}
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Axis {
 	Horizontal,
 	Vertical,
@@ -186,6 +263,24 @@ enum Terrain {
 	Rock,
 }
 
+impl Terrain {
+	fn bit(self) -> u64 {
+		match self {
+			Terrain::Ash => 0,
+			Terrain::Rock => 1,
+		}
+	}
+}
+
+impl From<Terrain> for char {
+	fn from(value: Terrain) -> Self {
+		match value {
+			Terrain::Ash => '.',
+			Terrain::Rock => '#',
+		}
+	}
+}
+
 impl TryFrom<char> for Terrain {
 	type Error = anyhow::Error;
 
@@ -250,11 +345,11 @@ mod tests {
 		#[test]
 		fn test_example_1_find_reflection() {
 			assert_eq!(
-				example_1().find_reflection(),
-				Some(Reflection {
+				example_1().find_reflections(0),
+				vec![Reflection {
 					axis: Axis::Vertical,
 					before_index: 5,
-				})
+				}]
 			);
 		}
 
@@ -278,12 +373,173 @@ mod tests {
 		#[test]
 		fn test_example_2_find_reflection() {
 			assert_eq!(
-				example_2().find_reflection(),
-				Some(Reflection {
+				example_2().find_reflections(0),
+				vec![Reflection {
 					axis: Axis::Horizontal,
 					before_index: 4,
-				})
+				}]
+			);
+		}
+
+		#[test]
+		fn test_example() {
+			let terrain = [
+				"#.##..##.",
+				"..#.##.#.",
+				"##......#",
+				"##......#",
+				"..#.##.#.",
+				"..##..##.",
+				"#.#.##.#.",
+				"",
+				"#...##..#",
+				"#....#..#",
+				"..##..###",
+				"#####.##.",
+				"#####.##.",
+				"..##..###",
+				"#....#..#",
+			];
+			assert_eq!(solve(terrain, 0).unwrap().score, 405);
+		}
+	}
+
+	mod part_2 {
+		use super::*;
+
+		#[test]
+		fn test_example_1_find_reflection() {
+			assert_eq!(
+				example_1().find_reflections(1),
+				vec![Reflection {
+					axis: Axis::Horizontal,
+					before_index: 3,
+				}]
+			);
+		}
+
+		#[test]
+		fn test_example_2_find_reflection() {
+			assert_eq!(
+				example_2().find_reflections(1),
+				vec![Reflection {
+					axis: Axis::Horizontal,
+					before_index: 1,
+				}]
 			);
 		}
+
+		#[test]
+		fn test_example() {
+			let terrain = [
+				"#.##..##.",
+				"..#.##.#.",
+				"##......#",
+				"##......#",
+				"..#.##.#.",
+				"..##..##.",
+				"#.#.##.#.",
+				"",
+				"#...##..#",
+				"#....#..#",
+				"..##..###",
+				"#####.##.",
+				"#####.##.",
+				"..##..###",
+				"#....#..#",
+			];
+			assert_eq!(solve(terrain, 1).unwrap().score, 400);
+		}
+	}
+
+	#[test]
+	fn test_find_reflection_axes() {
+		let masks = [0b101, 0b011, 0b011, 0b101, 0b111];
+		assert_eq!(find_reflection_axes(&masks, 0), vec![2]);
+		assert_eq!(find_reflection_axes(&masks, 1), vec![4]);
+		assert_eq!(find_reflection_axes(&masks, 2), vec![1]);
+		assert_eq!(find_reflection_axes(&masks, 3), vec![3]);
+	}
+
+	#[test]
+	fn test_draw() {
+		let region = example_1();
+		assert_eq!(
+			region.draw(&region.find_reflections(0)),
+			[
+				"#.##.|.##.",
+				"..#.#|#.#.",
+				"##...|...#",
+				"##...|...#",
+				"..#.#|#.#.",
+				"..##.|.##.",
+				"#.#.#|#.#.",
+			]
+			.join("\n")
+		);
+
+		let region = example_2();
+		assert_eq!(
+			region.draw(&region.find_reflections(0)),
+			[
+				"#...##..#",
+				"#....#..#",
+				"..##..###",
+				"#####.##.",
+				"---------",
+				"#####.##.",
+				"..##..###",
+				"#....#..#",
+			]
+			.join("\n")
+		);
+	}
+
+	#[test]
+	fn test_draw_crossing_axes() {
+		let region = Region::parse_many(["#..#", "....", "....", "#..#"]).unwrap();
+		let region = &region[0];
+		assert_eq!(
+			region.draw(&region.find_reflections(0)),
+			["#.|.#", "..|..", "--+--", "..|..", "#.|.#"].join("\n")
+		);
+	}
+
+	#[test]
+	fn test_summary() {
+		let terrain = [
+			"#.##..##.",
+			"..#.##.#.",
+			"##......#",
+			"##......#",
+			"..#.##.#.",
+			"..##..##.",
+			"#.#.##.#.",
+			"",
+			"#.#",
+			".#.",
+			"##.",
+			"",
+			"#..#",
+			"....",
+			"....",
+			"#..#",
+		];
+		let summary = solve(terrain, 0).unwrap();
+		assert_eq!(
+			summary,
+			Summary {
+				score: 5 + 200,
+				without_reflection: vec![1],
+				multiple_reflections: vec![(2, 2)],
+			}
+		);
+		assert_eq!(
+			summary.warnings(),
+			[
+				"region 1 has no reflection",
+				"region 2 has 2 reflections, only the first one is scored"
+			]
+		);
 	}
 }
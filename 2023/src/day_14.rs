@@ -1,45 +1,38 @@
-use std::{
-	collections::{HashMap, VecDeque},
-	fmt::Debug,
-};
+use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
 const INPUT: &'static str = include_str!("../../inputs/2023/day_14.txt");
 
+/// The tilts of a single spin cycle
+const SPIN_CYCLE: &str = "NWSE";
+
 pub fn main(part_two: bool) -> anyhow::Result<u32> {
-	solve(INPUT.lines(), part_two)
+	let (program, times) = match part_two {
+		false => ("N", 1),
+		true => (SPIN_CYCLE, 1000000000),
+	};
+	let program = crate::option::<String>(&format!("Tilt program ({program})"))?
+		.unwrap_or(program.to_string());
+	let times = crate::option(&format!("Repetitions ({times})"))?.unwrap_or(times);
+	let edge = match crate::option::<char>("Edge to measure the load on (N)")? {
+		Some(edge) => Direction::try_from(edge)?,
+		None => Direction::North,
+	};
+	solve(INPUT.lines(), &program, times, edge)
 }
 
-fn solve(lines: impl IntoIterator<Item = &'static str>, cycle: bool) -> anyhow::Result<u32> {
+fn solve(
+	lines: impl IntoIterator<Item = &'static str>,
+	program: &str,
+	times: u32,
+	edge: Direction,
+) -> anyhow::Result<u32> {
 	let mut plane: Plane = Plane::parse(lines)?;
-	if cycle {
-		plane.cycle(1000000000)
-	} else {
-		plane.tilt(Direction::North);
-	}
-	Ok(plane.total_load())
+	plane.run(&program.parse()?, times);
+	Ok(plane.load(edge))
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
-struct Point(i64, i64);
-
-impl Point {
-	fn new(x: u32, y: u32) -> Self {
-		Point(x as i64, y as i64)
-	}
-
-	fn add(&self, direction: Direction, length: u32) -> Self {
-		match direction {
-			Direction::North => Self(self.0, self.1 - length as i64),
-			Direction::South => Self(self.0, self.1 + length as i64),
-			Direction::East => Self(self.0 + length as i64, self.1),
-			Direction::West => Self(self.0 - length as i64, self.1),
-		}
-	}
-
-	fn is_inside_plane(&self, plane: &Plane) -> bool {
-		self.0 >= 0 && self.0 < plane.width as i64 && self.1 >= 0 && self.1 < plane.height as i64
-	}
-}
+struct Point(u32, u32);
 
 impl Debug for Point {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -54,19 +47,22 @@ enum Rock {
 }
 
 impl Rock {
-	fn from_char(char: char) -> Option<Self> {
+	fn from_char(char: char) -> anyhow::Result<Option<Self>> {
 		match char {
-			'.' => None,
-			'#' => Some(Self::Cube),
-			'O' => Some(Self::Sphere),
-			_ => panic!(), // WARN: Should semantically be an error
+			'.' => Ok(None),
+			'#' => Ok(Some(Self::Cube)),
+			'O' => Ok(Some(Self::Sphere)),
+			_ => Err(anyhow::anyhow!(
+				"expected one of '.', '#', 'O', got {}",
+				char
+			)),
 		}
 	}
 }
 
-impl Into<char> for &Rock {
-	fn into(self) -> char {
-		match self {
+impl From<&Rock> for char {
+	fn from(rock: &Rock) -> Self {
+		match rock {
 			Rock::Cube => '#',
 			Rock::Sphere => 'O',
 		}
@@ -81,49 +77,59 @@ enum Direction {
 	West,
 }
 
-impl Direction {
-	fn flip(&self) -> Direction {
-		match self {
-			Direction::North => Direction::South,
-			Direction::South => Direction::North,
-			Direction::East => Direction::West,
-			Direction::West => Direction::East,
+impl TryFrom<char> for Direction {
+	type Error = anyhow::Error;
+
+	fn try_from(value: char) -> Result<Self, Self::Error> {
+		match value {
+			'N' => Ok(Direction::North),
+			'E' => Ok(Direction::East),
+			'S' => Ok(Direction::South),
+			'W' => Ok(Direction::West),
+			_ => Err(anyhow::anyhow!(
+				"expected one of 'N', 'E', 'S', 'W', got {}",
+				value
+			)),
 		}
 	}
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-struct RockColumn {
-	start: Point,
-	direction: Direction,
-	/// Amount of spherical rocks found
-	spheres_count: u32,
-	/// Total steps done before hitting a cube or the edge of the plane
-	total_length: u32,
-}
+/// Tilts that are applied one after another, e.g. `"NWSE"` for a spin cycle
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct TiltProgram(Vec<Direction>);
 
-impl RockColumn {
-	pub fn end(&self) -> Point {
-		self.start.add(self.direction, self.total_length - 1)
+impl FromStr for TiltProgram {
+	type Err = anyhow::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let tilts = s
+			.chars()
+			.map(Direction::try_from)
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		Ok(TiltProgram(tilts))
 	}
 }
 
+/// Rows are stored as bitmasks, so a plane can be at most this wide
+const MAX_WIDTH: u32 = u128::BITS;
+
+/// Every row is a bitmask where bit `x` is set if there is a rock in column `x`
 #[derive(Clone, PartialEq, Eq)]
 struct Plane {
 	width: u32,
 	height: u32,
-	rocks: HashMap<Point, Rock>,
+	cubes: Vec<u128>,
+	spheres: Vec<u128>,
 }
 
 impl Debug for Plane {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		writeln!(f, "")?;
+		writeln!(f)?;
 		for y in 0..self.height {
 			let line = (0..self.width)
 				.map(|x| {
-					self.rocks
-						.get(&Point::new(x, y))
-						.map_or('.', |rock| rock.into())
+					self.rock_at(Point(x, y))
+						.map_or('.', |rock| char::from(&rock))
 				})
 				.collect::<String>();
 			writeln!(f, "{}", line)?;
@@ -133,137 +139,179 @@ impl Debug for Plane {
 }
 
 impl Plane {
+	pub fn new(
+		width: u32,
+		height: u32,
+		rocks: impl IntoIterator<Item = (Point, Rock)>,
+	) -> anyhow::Result<Self> {
+		anyhow::ensure!(
+			width <= MAX_WIDTH,
+			"plane is wider than {} tiles",
+			MAX_WIDTH
+		);
+
+		let mut plane = Plane {
+			width,
+			height,
+			cubes: vec![0; height as usize],
+			spheres: vec![0; height as usize],
+		};
+		for (Point(x, y), rock) in rocks {
+			anyhow::ensure!(
+				x < width && y < height,
+				"{:?} is outside of the plane",
+				Point(x, y)
+			);
+			let row = match rock {
+				Rock::Cube => &mut plane.cubes[y as usize],
+				Rock::Sphere => &mut plane.spheres[y as usize],
+			};
+			*row |= 1 << x;
+		}
+		Ok(plane)
+	}
+
 	pub fn parse(lines: impl IntoIterator<Item = &'static str>) -> anyhow::Result<Self> {
 		let mut lines = lines.into_iter().peekable();
 
-		let width = lines.peek().unwrap().len().try_into()?;
+		let width = lines.peek().map_or(0, |line| line.len()).try_into()?;
 		let mut height = 0;
-		let rocks: HashMap<Point, Rock> = lines
-			.inspect(|_| height += 1)
-			.enumerate()
-			.flat_map(|(y, line)| {
-				line.chars()
-					.enumerate()
-					.filter_map(move |(x, char)| {
-						Some((
-							Point(x.try_into().unwrap(), y.try_into().unwrap()),
-							Rock::from_char(char)?,
-						))
-					})
-					.collect::<HashMap<Point, Rock>>() // TODO: do not collect inbetween
-			})
-			.collect();
+		let mut rocks = Vec::new();
+		for (y, line) in lines.enumerate() {
+			height += 1;
+			for (x, char) in line.chars().enumerate() {
+				if let Some(rock) = Rock::from_char(char)? {
+					rocks.push((Point(x.try_into()?, y.try_into()?), rock));
+				}
+			}
+		}
 
-		Ok(Plane {
-			width,
-			height,
-			rocks,
-		})
+		Plane::new(width, height, rocks)
 	}
 
-	fn cycle_once(&mut self) {
-		self.tilt(Direction::North);
-		self.tilt(Direction::West);
-		self.tilt(Direction::South);
-		self.tilt(Direction::East);
+	fn rock_at(&self, Point(x, y): Point) -> Option<Rock> {
+		let bit = 1 << x;
+		if self.cubes[y as usize] & bit != 0 {
+			Some(Rock::Cube)
+		} else if self.spheres[y as usize] & bit != 0 {
+			Some(Rock::Sphere)
+		} else {
+			None
+		}
 	}
 
-	fn cycle(&mut self, times: u32) {
-		let mut cycle_history = VecDeque::from([self.clone()]);
-		for t in 0..times {
-			self.cycle_once();
-			if let Some(i) = cycle_history.iter().position(|plane| plane == self) {
-				let remaining_cycles = (times - t - 1) % (i as u32 + 1);
-				for _ in 0..remaining_cycles {
-					self.cycle_once();
+	fn run_once(&mut self, program: &TiltProgram) {
+		for &direction in &program.0 {
+			self.tilt(direction);
+		}
+	}
+
+	/// Runs `program` `times` times. As soon as a state repeats, the remaining runs are skipped
+	/// except for the ones needed to end up at the same point in the loop.
+	fn run(&mut self, program: &TiltProgram, times: u32) {
+		// cubes never move, so the spheres alone identify a state
+		let mut history = HashMap::from([(self.spheres.clone(), 0)]);
+		for t in 1..=times {
+			self.run_once(program);
+			if let Some(previous) = history.insert(self.spheres.clone(), t) {
+				let remaining_runs = (times - t) % (t - previous);
+				for _ in 0..remaining_runs {
+					self.run_once(program);
 				}
 				break;
 			}
-			cycle_history.push_front(self.clone());
 		}
 	}
 
 	fn tilt(&mut self, direction: Direction) {
-		let width = self.width;
-		let height = self.height;
-		let direction = direction.flip();
-		let mut stack: Vec<Point> = match direction {
-			Direction::South => (0..width).map(|x| Point::new(x, 0)).collect(),
-			Direction::North => (0..width).map(|x| Point::new(x, height - 1)).collect(),
-			Direction::East => (0..height).map(|y| Point::new(0, y)).collect(),
-			Direction::West => (0..height).map(|y| Point::new(width - 1, y)).collect(),
-		};
-
-		while let Some(point) = stack.pop() {
-			if self.rocks.get(&point) == Some(&Rock::Cube) {
-				let next_point = point.add(direction, 1);
-				if next_point.is_inside_plane(self) {
-					stack.push(next_point);
-				}
-				continue;
-			}
-
-			let rock_column = self.crawl_rock_column(point, direction);
-			self.collapse_rock_column(rock_column);
-
-			let next_point = rock_column.end().add(direction, 2);
-			if next_point.is_inside_plane(self) {
-				stack.push(next_point);
-			}
+		let height = self.height as usize;
+		match direction {
+			Direction::North => self.tilt_rows(&(0..height).collect::<Vec<_>>()),
+			Direction::South => self.tilt_rows(&(0..height).rev().collect::<Vec<_>>()),
+			Direction::West => self.tilt_columns(true),
+			Direction::East => self.tilt_columns(false),
 		}
 	}
 
-	fn collapse_rock_column(&mut self, rock_column: RockColumn) {
-		(0..rock_column.total_length).for_each(|offset| {
-			let point = rock_column.start.add(rock_column.direction, offset);
-			if offset < rock_column.spheres_count {
-				self.rocks.insert(point, Rock::Sphere);
-			} else {
-				self.rocks.remove(&point);
-			}
-		})
-	}
-
-	fn crawl_rock_column(&self, start: Point, direction: Direction) -> RockColumn {
-		let mut spheres_count = 0;
-		let mut offset: u32 = 0;
-		loop {
-			if match direction {
-				Direction::North => start.1 - offset as i64 == -1,
-				Direction::South => start.1 + offset as i64 == self.height.into(),
-				Direction::West => start.0 - offset as i64 == -1,
-				Direction::East => start.0 + offset as i64 == self.width.into(),
-			} {
-				break;
-			}
-
-			let point = start.add(direction, offset);
-			match self.rocks.get(&point) {
-				Some(Rock::Sphere) => spheres_count += 1,
-				Some(Rock::Cube) => {
-					break;
-				}
-				None => (),
+	/// Moves every sphere as far as possible towards the first row of `order`. All columns are
+	/// moved at once, row by row, so that every row moves onto already settled rows.
+	fn tilt_rows(&mut self, order: &[usize]) {
+		for i in 1..order.len() {
+			let mut moving = std::mem::take(&mut self.spheres[order[i]]);
+			let mut landing = i;
+			while landing > 0 && moving != 0 {
+				let next = order[landing - 1];
+				let blocked = self.cubes[next] | self.spheres[next];
+				self.spheres[order[landing]] |= moving & blocked;
+				moving &= !blocked;
+				landing -= 1;
 			}
+			self.spheres[order[landing]] |= moving;
+		}
+	}
 
-			offset += 1
+	/// Moves every sphere as far as possible towards the lowest (`towards_low`) or highest column
+	fn tilt_columns(&mut self, towards_low: bool) {
+		for (spheres, &cubes) in self.spheres.iter_mut().zip(&self.cubes) {
+			*spheres = tilt_row(*spheres, cubes, self.width, towards_low);
 		}
-		RockColumn {
-			start,
-			direction,
-			spheres_count,
-			total_length: offset,
+	}
+
+	/// Every sphere weighs as much as its distance to the opposite edge of the plane plus one
+	pub fn load(&self, edge: Direction) -> u32 {
+		match edge {
+			Direction::North => self.row_load((1..=self.height).rev()),
+			Direction::South => self.row_load(1..=self.height),
+			Direction::West => self.column_load((1..=self.width).rev()),
+			Direction::East => self.column_load(1..=self.width),
 		}
 	}
 
-	pub fn total_load(&self) -> u32 {
-		self.rocks
+	fn row_load(&self, loads: impl Iterator<Item = u32>) -> u32 {
+		self.spheres
 			.iter()
-			.filter(|(_, rock)| **rock == Rock::Sphere)
-			.fold(0, |total_load, (point, _)| {
-				// WARN: Converting from `i64` to `u32` can technically panic
-				total_load + self.height - point.1 as u32
+			.zip(loads)
+			.map(|(spheres, load)| spheres.count_ones() * load)
+			.sum()
+	}
+
+	fn column_load(&self, loads: impl Iterator<Item = u32>) -> u32 {
+		let all_spheres = self.spheres.iter().fold(0, |all, spheres| all | spheres);
+		(0..self.width)
+			.zip(loads)
+			.filter(|(x, _)| all_spheres & 1 << x != 0)
+			.map(|(x, load)| {
+				let count = self.spheres.iter().filter(|&spheres| spheres & 1 << x != 0);
+				count.count() as u32 * load
 			})
+			.sum()
+	}
+}
+
+/// Splits the row at every cube and packs the spheres of every segment to one of its ends
+fn tilt_row(spheres: u128, cubes: u128, width: u32, towards_low: bool) -> u128 {
+	let mut tilted = 0;
+	let mut start = 0;
+	while start < width {
+		let end = match cubes >> start {
+			0 => width,
+			rest => start + rest.trailing_zeros(),
+		};
+		let count = (spheres & bit_range(start, end)).count_ones();
+		tilted |= match towards_low {
+			true => bit_range(start, start + count),
+			false => bit_range(end - count, end),
+		};
+		start = end + 1;
+	}
+	tilted
+}
+
+/// Bitmask with the bits `start..end` set
+fn bit_range(start: u32, end: u32) -> u128 {
+	match end - start {
+		0 => 0,
+		length => (u128::MAX >> (u128::BITS - length)) << start,
 	}
 }
 
@@ -272,10 +320,10 @@ mod tests {
 	use super::*;
 
 	fn example_1() -> Plane {
-		Plane {
-			width: 10,
-			height: 10,
-			rocks: [
+		Plane::new(
+			10,
+			10,
+			[
 				(Point(0, 0), Rock::Sphere),
 				(Point(5, 0), Rock::Cube),
 				(Point(0, 1), Rock::Sphere),
@@ -311,9 +359,9 @@ mod tests {
 				(Point(1, 9), Rock::Sphere),
 				(Point(2, 9), Rock::Sphere),
 				(Point(5, 9), Rock::Cube),
-			]
-			.into(),
-		}
+			],
+		)
+		.unwrap()
 	}
 
 	#[test]
@@ -378,7 +426,7 @@ mod tests {
 	#[test]
 	fn test_example_1_cycle_once() {
 		let mut plane = example_1();
-		plane.cycle(1);
+		plane.run(&SPIN_CYCLE.parse().unwrap(), 1);
 		// WARN: this should use the constructor instead of `parse`
 		let expected = Plane::parse([
 			".....#....",
@@ -399,7 +447,7 @@ mod tests {
 	#[test]
 	fn test_example_1_cycle_twice() {
 		let mut plane = example_1();
-		plane.cycle(2);
+		plane.run(&SPIN_CYCLE.parse().unwrap(), 2);
 		// WARN: this should use the constructor instead of `parse`
 		let expected = Plane::parse([
 			".....#....",
@@ -420,7 +468,7 @@ mod tests {
 	#[test]
 	fn test_example_1_cycle_thrice() {
 		let mut plane = example_1();
-		plane.cycle(3);
+		plane.run(&SPIN_CYCLE.parse().unwrap(), 3);
 		// WARN: this should use the constructor instead of `parse`
 		let expected = Plane::parse([
 			".....#....",
@@ -438,13 +486,117 @@ mod tests {
 		assert_eq!(plane, expected);
 	}
 
+	#[test]
+	fn test_example_1_tilt_west() {
+		let mut plane = example_1();
+		plane.tilt(Direction::West);
+		let expected = Plane::parse([
+			"O....#....",
+			"OOO.#....#",
+			".....##...",
+			"OO.#OO....",
+			"OO......#.",
+			"O.#O...#.#",
+			"O....#OO..",
+			"O.........",
+			"#....###..",
+			"#OO..#....",
+		])
+		.unwrap();
+		assert_eq!(plane, expected);
+	}
+
+	#[test]
+	fn test_example_1_tilt_east() {
+		let mut plane = example_1();
+		plane.tilt(Direction::East);
+		let expected = Plane::parse([
+			"....O#....",
+			".OOO#....#",
+			".....##...",
+			".OO#....OO",
+			"......OO#.",
+			".O#...O#.#",
+			"....O#..OO",
+			".........O",
+			"#....###..",
+			"#..OO#....",
+		])
+		.unwrap();
+		assert_eq!(plane, expected);
+	}
+
+	#[test]
+	fn test_tilt_row() {
+		let spheres = 0b0100_1010;
+		let cubes = 0b0001_0000;
+		assert_eq!(tilt_row(spheres, cubes, 8, true), 0b0010_0011);
+		assert_eq!(tilt_row(spheres, cubes, 8, false), 0b1000_1100);
+	}
+
+	#[test]
+	fn test_parse_tilt_program() {
+		use Direction::*;
+		assert_eq!(
+			"NNEE".parse::<TiltProgram>().unwrap(),
+			TiltProgram(vec![North, North, East, East])
+		);
+		assert!("NWXE".parse::<TiltProgram>().is_err());
+	}
+
+	#[test]
+	fn test_run_matches_repeated_run_once() {
+		let program = "NE".parse().unwrap();
+		let mut skipping = example_1();
+		skipping.run(&program, 1000);
+		let mut naive = example_1();
+		for _ in 0..1000 {
+			naive.run_once(&program);
+		}
+		assert_eq!(skipping, naive);
+	}
+
+	#[test]
+	fn test_load() {
+		let plane = Plane::parse(["O..#", "...O", ".O.."]).unwrap();
+		assert_eq!(plane.load(Direction::North), 3 + 2 + 1);
+		assert_eq!(plane.load(Direction::South), 1 + 2 + 3);
+		assert_eq!(plane.load(Direction::West), 4 + 1 + 3);
+		assert_eq!(plane.load(Direction::East), 1 + 4 + 2);
+	}
+
+	#[test]
+	fn test_example_1_nnee() {
+		let lines = [
+			"O....#....",
+			"O.OO#....#",
+			".....##...",
+			"OO.#O....O",
+			".O.....O#.",
+			"O.#..O.#.#",
+			"..O..#O..O",
+			".......O..",
+			"#....###..",
+			"#OO..#....",
+		];
+		assert_eq!(
+			solve(lines, "NNEE", 1, Direction::East).unwrap(),
+			solve(lines, "NE", 1, Direction::East).unwrap()
+		);
+	}
+
+	#[test]
+	fn test_parse_invalid_rock() {
+		assert!(Plane::parse(["O..", ".X."]).is_err());
+	}
+
 	mod part_1 {
 		use super::*;
 		#[test]
 		fn test_example_1() {
 			let mut plane = example_1();
 			plane.tilt(Direction::North);
-			assert_eq!(plane.total_load(), 136)
+			assert_eq!(plane.load(Direction::North), 136)
 		}
 	}
 
@@ -454,20 +606,8 @@ mod tests {
 		#[test]
 		fn test_example_1() {
 			let mut plane = example_1();
-			plane.cycle(1000000000);
-			assert_eq!(plane.total_load(), 64)
-		}
-	}
-
-	mod point {
-		use super::*;
-
-		#[test]
-		fn test_add() {
-			assert_eq!(Point(10, 10).add(Direction::North, 5), Point(10, 5));
-			assert_eq!(Point(10, 10).add(Direction::South, 5), Point(10, 15));
-			assert_eq!(Point(10, 10).add(Direction::East, 5), Point(15, 10));
-			assert_eq!(Point(10, 10).add(Direction::West, 5), Point(5, 10));
+			plane.run(&SPIN_CYCLE.parse().unwrap(), 1000000000);
+			assert_eq!(plane.load(Direction::North), 64)
 		}
 	}
 }
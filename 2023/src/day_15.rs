@@ -1,4 +1,14 @@
-use std::collections::HashMap;
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::Display,
+	io::{BufRead, Write},
+};
+
+use typed_arena::Arena;
+
+use hash::{Hasher, Preimages};
+
+mod hash;
 
 const INPUT: &'static str = include_str!("../../inputs/2023/day_15.txt");
 
@@ -7,14 +17,61 @@ pub fn main(part_two: bool) -> anyhow::Result<u32> {
 	let steps = line.split(',');
 
 	match part_two {
-		false => solve_part_1(steps),
-		true => solve_part_2(steps),
+		false => {
+			let multiplier = crate::option("HASH multiplier (17)")?.unwrap_or(17);
+			let modulus = crate::option("HASH modulus (256)")?.unwrap_or(256);
+			let hasher = Hasher::new(multiplier, modulus)?;
+			if let Some(target) = crate::option("Find labels with hash (none)")? {
+				print_preimages(hasher, target)?;
+			}
+			solve_part_1(steps, &hasher)
+		}
+		true => {
+			if crate::option("Interactive mode? (false)")?.unwrap_or(false) {
+				println!("Enter one step per line, or an empty line to stop");
+				return repl(std::io::stdin().lock(), std::io::stdout());
+			}
+			let steps = steps
+				.map(Step::try_from)
+				.collect::<anyhow::Result<Vec<_>>>()?;
+			if crate::option("Print walkthrough? (false)")?.unwrap_or(false) {
+				println!("{}", walkthrough(steps.iter().copied()));
+			}
+			let n = crate::option("Replay up to step (all)")?.unwrap_or(steps.len());
+			if let Some(m) = crate::option::<usize>("Compare with the state after step (none)")? {
+				let before = replay(steps.iter().copied(), m);
+				let after = replay(steps.iter().copied(), n);
+				for line in before.diff(&after) {
+					println!("{line}");
+				}
+			}
+			solve_part_2(line.split(',').take(n))
+		}
 	}
 }
 
-pub fn solve_part_1(steps: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u32> {
+fn print_preimages(hasher: Hasher, target: u32) -> anyhow::Result<()> {
+	let alphabet = crate::option::<String>("Alphabet (a-z)")?
+		.unwrap_or("abcdefghijklmnopqrstuvwxyz".to_string());
+	let count = crate::option("Number of labels (1)")?.unwrap_or(1);
+	let preimages = Preimages::new(hasher, target, &alphabet)?;
+	let labels = match count {
+		1 => preimages.shortest().into_iter().collect(),
+		count => preimages.labels(count),
+	};
+	match labels.is_empty() {
+		true => println!("No label hashes to {target}"),
+		false => println!("{}", labels.join(",")),
+	}
+	Ok(())
+}
+
+fn solve_part_1(
+	steps: impl IntoIterator<Item = &'static str>,
+	hasher: &Hasher,
+) -> anyhow::Result<u32> {
 	let steps = steps.into_iter();
-	steps.map(hash).map(|step| Ok(u32::from(step?))).sum()
+	steps.map(|step| hasher.hash(step)).sum()
 }
 
 pub fn solve_part_2(steps: impl IntoIterator<Item = &'static str>) -> anyhow::Result<u32> {
@@ -23,13 +80,80 @@ pub fn solve_part_2(steps: impl IntoIterator<Item = &'static str>) -> anyhow::Re
 	boxes.total_focusing_power()
 }
 
-fn hash(input: &str) -> anyhow::Result<u8> {
-	input.chars().fold(Ok(0u8), |acc, char| {
-		let ascii_code: u8 = char.try_into()?;
-		Ok(acc?.wrapping_add(ascii_code).wrapping_mul(17))
+/// Applies steps typed one per line, and prints the box each step changed and the total focusing
+/// power after it. Malformed steps are reported and skipped. Stops at the end of the input or at
+/// an empty line, returning the total focusing power.
+fn repl(input: impl BufRead, mut output: impl Write) -> anyhow::Result<u32> {
+	// the boxes borrow their labels, so every distinct label is kept once for the whole session
+	let labels = Arena::new();
+	let mut interned = HashSet::new();
+	let mut boxes = LensBoxes::new();
+	let mut total_focusing_power = 0;
+	for line in input.lines() {
+		let line = line?;
+		let line = line.trim();
+		if line.is_empty() {
+			break;
+		}
+
+		let step = match Step::try_from(line) {
+			Ok(step) => step,
+			Err(err) => {
+				writeln!(output, "Invalid step `{line}`: {err}")?;
+				continue;
+			}
+		};
+		let label = match interned.get(step.label) {
+			Some(&label) => label,
+			None => {
+				let label: &str = labels.alloc(step.label.to_string());
+				interned.insert(label);
+				label
+			}
+		};
+		boxes.apply(Step { label, ..step });
+		total_focusing_power = boxes.total_focusing_power()?;
+
+		let box_index = step.label_hash;
+		let lenses = &boxes.0[usize::from(box_index)];
+		match lenses.is_empty() {
+			true => writeln!(output, "Box {box_index}: (empty)")?,
+			false => writeln!(output, "Box {box_index}: {lenses}")?,
+		}
+		writeln!(output, "Total focusing power: {total_focusing_power}")?;
+	}
+	Ok(total_focusing_power)
+}
+
+/// Applies `steps` one by one, yielding each step together with the boxes right after it
+fn trace<'l>(
+	steps: impl IntoIterator<Item = Step<'l>>,
+) -> impl Iterator<Item = (Step<'l>, LensBoxes<'l>)> {
+	steps.into_iter().scan(LensBoxes::new(), |boxes, step| {
+		boxes.apply(step);
+		Some((step, boxes.clone()))
 	})
 }
 
+/// Formats the trace like the walkthrough in the puzzle description
+fn walkthrough<'l>(steps: impl IntoIterator<Item = Step<'l>>) -> String {
+	trace(steps)
+		.map(|(step, boxes)| format!("After \"{step}\":\n{boxes}"))
+		.collect::<Vec<_>>()
+		.join("\n\n")
+}
+
+/// Returns the boxes after the first `n` steps
+fn replay<'l>(steps: impl IntoIterator<Item = Step<'l>>, n: usize) -> LensBoxes<'l> {
+	steps.into_iter().take(n).collect()
+}
+
+/// Hashes a label to the index of its box
+fn hash(input: &str) -> anyhow::Result<u8> {
+	// this cannot truncate because the default modulus is 256
+	Ok(Hasher::default().hash(input)? as u8)
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 struct Lens<'a> {
 	label: &'a str,
@@ -45,29 +169,55 @@ impl<'l> Lens<'l> {
 	}
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
-struct LensBox<'l>(Vec<Lens<'l>>);
+/// The lenses of one box in slot order. Removed lenses leave a gap behind, which keeps removal
+/// O(1). The gaps are closed once they outnumber the lenses, so they never take up more than
+/// half of the slots.
+#[derive(Clone, Debug, Default)]
+struct LensBox<'l> {
+	slots: Vec<Option<Lens<'l>>>,
+	/// Index into `slots` of every lens in the box
+	positions: HashMap<&'l str, usize>,
+}
 
 impl<'l> LensBox<'l> {
 	fn remove(&mut self, label: &str) {
-		self.0
-			.iter()
-			.position(|lens| lens.label == label)
-			.inspect(|&i| {
-				self.0.remove(i);
-			});
+		if let Some(i) = self.positions.remove(label) {
+			self.slots[i] = None;
+			if self.slots.len() > 2 * self.positions.len() {
+				self.compact();
+			}
+		}
 	}
 
 	fn insert(&mut self, label: &'l str, focal_length: u8) {
-		match self.0.iter_mut().find(|lens| lens.label == label) {
-			Some(lens) => lens.focal_length = focal_length,
-			None => self.0.push(Lens::new(label, focal_length)),
+		match self.positions.get(label) {
+			// this cannot panic because `positions` only points to occupied slots
+			Some(&i) => self.slots[i].as_mut().unwrap().focal_length = focal_length,
+			None => {
+				self.positions.insert(label, self.slots.len());
+				self.slots.push(Some(Lens::new(label, focal_length)));
+			}
 		}
 	}
 
+	fn compact(&mut self) {
+		self.slots.retain(Option::is_some);
+		for (i, lens) in self.slots.iter().flatten().enumerate() {
+			self.positions.insert(lens.label, i);
+		}
+	}
+
+	/// Returns the lenses in slot order
+	fn lenses(&self) -> impl Iterator<Item = &Lens<'l>> {
+		self.slots.iter().flatten()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.positions.is_empty()
+	}
+
 	fn focusing_power(&self, box_index: u8) -> anyhow::Result<u32> {
-		self.0
-			.iter()
+		self.lenses()
 			.enumerate()
 			.map(|(slot_index, lens)| {
 				Ok((u32::from(box_index) + 1)
@@ -78,37 +228,104 @@ impl<'l> LensBox<'l> {
 	}
 }
 
+impl PartialEq for LensBox<'_> {
+	fn eq(&self, other: &Self) -> bool {
+		self.lenses().eq(other.lenses())
+	}
+}
+
+impl Eq for LensBox<'_> {}
+
+impl Display for LensBox<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let lenses = self
+			.lenses()
+			.map(|lens| format!("[{} {}]", lens.label, lens.focal_length))
+			.collect::<Vec<_>>();
+		write!(f, "{}", lenses.join(" "))
+	}
+}
+
 impl<'l, T> From<T> for LensBox<'l>
 where
 	T: Into<Vec<Lens<'l>>>,
 {
 	fn from(value: T) -> Self {
-		LensBox(value.into())
+		let mut lens_box = LensBox::default();
+		for lens in value.into() {
+			lens_box.insert(lens.label, lens.focal_length);
+		}
+		lens_box
 	}
 }
 
-#[derive(Clone, Debug, Default, PartialEq, Eq)]
-struct LensBoxes<'l>(HashMap<u8, LensBox<'l>>);
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct LensBoxes<'l>([LensBox<'l>; 256]);
 
 impl<'l> LensBoxes<'l> {
 	fn new() -> Self {
-		LensBoxes(HashMap::new())
+		LensBoxes(std::array::from_fn(|_| LensBox::default()))
+	}
+
+	fn apply(&mut self, step: Step<'l>) {
+		let boxx = &mut self.0[usize::from(step.label_hash)];
+		match step.operation {
+			StepOperation::Remove => boxx.remove(step.label),
+			StepOperation::Insert(focal_length) => boxx.insert(step.label, focal_length),
+		}
+	}
+
+	/// Returns the boxes with their index
+	fn indexed(&self) -> impl Iterator<Item = (u8, &LensBox<'l>)> {
+		(0..=u8::MAX).zip(self.0.iter())
+	}
+
+	/// Returns the boxes that hold lenses, ordered by index
+	fn occupied(&self) -> Vec<(u8, &LensBox<'l>)> {
+		self.indexed()
+			.filter(|(_, lenses)| !lenses.is_empty())
+			.collect()
+	}
+
+	/// Describes every box whose lenses differ between `self` and `other`, one line per box
+	fn diff(&self, other: &LensBoxes<'l>) -> Vec<String> {
+		let describe = |lenses: &LensBox| match lenses.is_empty() {
+			true => "(empty)".to_string(),
+			false => lenses.to_string(),
+		};
+		self.indexed()
+			.zip(other.0.iter())
+			.filter(|((_, before), after)| before != after)
+			.map(|((box_index, before), after)| {
+				format!(
+					"Box {box_index}: {} -> {}",
+					describe(before),
+					describe(after)
+				)
+			})
+			.collect()
 	}
 
 	fn total_focusing_power(&self) -> anyhow::Result<u32> {
-		self.0
-			.iter()
-			.map(|(&box_index, lenses)| lenses.focusing_power(box_index))
+		self.indexed()
+			.map(|(box_index, lenses)| lenses.focusing_power(box_index))
 			.sum()
 	}
 }
 
-impl<'l, T> From<T> for LensBoxes<'l>
-where
-	T: Into<HashMap<u8, LensBox<'l>>>,
-{
-	fn from(value: T) -> Self {
-		LensBoxes(value.into())
+impl Default for LensBoxes<'_> {
+	fn default() -> Self {
+		LensBoxes::new()
+	}
+}
+
+impl<'l, const N: usize> From<[(u8, LensBox<'l>); N]> for LensBoxes<'l> {
+	fn from(value: [(u8, LensBox<'l>); N]) -> Self {
+		let mut boxes = LensBoxes::new();
+		for (box_index, lenses) in value {
+			boxes.0[usize::from(box_index)] = lenses;
+		}
+		boxes
 	}
 }
 
@@ -116,16 +333,24 @@ impl<'l> FromIterator<Step<'l>> for LensBoxes<'l> {
 	fn from_iter<T: IntoIterator<Item = Step<'l>>>(iter: T) -> Self {
 		let mut boxes: LensBoxes<'l> = LensBoxes::new();
 		for step in iter {
-			let boxx: &mut LensBox<'l> = boxes.0.entry(step.label_hash).or_default();
-			match step.operation {
-				StepOperation::Remove => boxx.remove(step.label),
-				StepOperation::Insert(focal_length) => boxx.insert(step.label, focal_length),
-			}
+			boxes.apply(step);
 		}
 		boxes
 	}
 }
 
+/// Lists the boxes that hold lenses, like `Box 0: [rn 1] [cm 2]`
+impl Display for LensBoxes<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let boxes = self
+			.occupied()
+			.into_iter()
+			.map(|(box_index, lenses)| format!("Box {box_index}: {lenses}"))
+			.collect::<Vec<_>>();
+		write!(f, "{}", boxes.join("\n"))
+	}
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 struct Step<'l> {
 	label: &'l str,
@@ -154,6 +379,12 @@ impl<'l> TryFrom<&'l str> for Step<'l> {
 	}
 }
 
+impl Display for Step<'_> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}{}", self.label, self.operation)
+	}
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum StepOperation {
 	Remove,
@@ -168,7 +399,7 @@ impl TryFrom<&str> for StepOperation {
 			"cannot parse `StepOperation` from empty string"
 		))?;
 
-		match (opcode, &s[1..]) {
+		match (opcode, &s[opcode.len_utf8()..]) {
 			('-', "") => Ok(Self::Remove),
 			('-', _) => Err(anyhow::anyhow!(
 				"remove operation cannot have characters following the `-`"
@@ -176,7 +407,11 @@ impl TryFrom<&str> for StepOperation {
 			('=', "") => Err(anyhow::anyhow!(
 				"insert operation is missing a focal length"
 			)),
-			('=', focal_length) => Ok(Self::Insert(focal_length.parse()?)),
+			('=', focal_length) => {
+				Ok(Self::Insert(focal_length.parse().map_err(|err| {
+					anyhow::anyhow!("invalid focal length `{focal_length}`: {err}")
+				})?))
+			}
 			(opcode, _) => Err(anyhow::anyhow!(format!(
 				"unknown operation `{opcode}`, expected `-` or `=`"
 			))),
@@ -184,10 +419,28 @@ impl TryFrom<&str> for StepOperation {
 	}
 }
 
+impl Display for StepOperation {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			StepOperation::Remove => write!(f, "-"),
+			StepOperation::Insert(focal_length) => write!(f, "={focal_length}"),
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	const EXAMPLE: &str = "rn=1,cm-,qp=3,cm=2,qp-,pc=4,ot=9,ab=5,pc-,pc=6,ot=7";
+
+	fn example_steps() -> Vec<Step<'static>> {
+		EXAMPLE
+			.split(',')
+			.map(|step| step.try_into().unwrap())
+			.collect()
+	}
+
 	#[test]
 	fn test_hash() {
 		assert_eq!(hash("HASH").unwrap(), 52);
@@ -218,4 +471,230 @@ mod tests {
 		]);
 		assert_eq!(boxes.total_focusing_power().unwrap(), 145);
 	}
+
+	#[test]
+	fn test_solve_part_1() {
+		assert_eq!(
+			solve_part_1(EXAMPLE.split(','), &Hasher::default()).unwrap(),
+			1320
+		);
+		assert!(solve_part_1(["ok", "nön"], &Hasher::default()).is_err());
+	}
+
+	#[test]
+	fn test_same_box_adversarial_input() {
+		let labels = Preimages::new(Hasher::default(), 7, "abcdefghijklmnopqrstuvwxyz")
+			.unwrap()
+			.labels(50);
+		let steps = labels
+			.iter()
+			.map(|label| Step::new(label, StepOperation::Insert(1)).unwrap());
+		let boxes = steps.collect::<LensBoxes>();
+		let occupied = boxes.occupied();
+		assert_eq!(occupied.len(), 1);
+		assert_eq!(occupied[0].0, 7);
+		assert_eq!(occupied[0].1.lenses().count(), 50);
+	}
+
+	#[test]
+	fn test_step_display() {
+		for step in EXAMPLE.split(',') {
+			assert_eq!(Step::try_from(step).unwrap().to_string(), step);
+		}
+	}
+
+	#[test]
+	fn test_walkthrough() {
+		let walkthrough = walkthrough(example_steps());
+		let expected = [
+			"After \"rn=1\":\nBox 0: [rn 1]",
+			"After \"cm-\":\nBox 0: [rn 1]",
+			"After \"qp=3\":\nBox 0: [rn 1]\nBox 1: [qp 3]",
+			"After \"cm=2\":\nBox 0: [rn 1] [cm 2]\nBox 1: [qp 3]",
+			"After \"qp-\":\nBox 0: [rn 1] [cm 2]",
+			"After \"pc=4\":\nBox 0: [rn 1] [cm 2]\nBox 3: [pc 4]",
+			"After \"ot=9\":\nBox 0: [rn 1] [cm 2]\nBox 3: [pc 4] [ot 9]",
+			"After \"ab=5\":\nBox 0: [rn 1] [cm 2]\nBox 3: [pc 4] [ot 9] [ab 5]",
+			"After \"pc-\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 9] [ab 5]",
+			"After \"pc=6\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 9] [ab 5] [pc 6]",
+			"After \"ot=7\":\nBox 0: [rn 1] [cm 2]\nBox 3: [ot 7] [ab 5] [pc 6]",
+		];
+		assert_eq!(walkthrough, expected.join("\n\n"));
+	}
+
+	#[test]
+	fn test_replay() {
+		let steps = example_steps();
+		assert_eq!(replay(steps.iter().copied(), 0).to_string(), "");
+		assert_eq!(
+			replay(steps.iter().copied(), 3).to_string(),
+			"Box 0: [rn 1]\nBox 1: [qp 3]"
+		);
+		assert_eq!(
+			replay(steps.iter().copied(), 100)
+				.total_focusing_power()
+				.unwrap(),
+			145
+		);
+	}
+
+	#[test]
+	fn test_diff() {
+		let steps = example_steps();
+		let before = replay(steps.iter().copied(), 4);
+		let after = replay(steps.iter().copied(), 6);
+		assert_eq!(
+			before.diff(&after),
+			["Box 1: [qp 3] -> (empty)", "Box 3: (empty) -> [pc 4]"]
+		);
+		assert!(after.diff(&after).is_empty());
+	}
+
+	#[test]
+	fn test_lens_box_slot_order() {
+		let mut lenses = LensBox::default();
+		for label in ["a", "b", "c", "d"] {
+			lenses.insert(label, 1);
+		}
+		lenses.remove("b");
+		lenses.remove("missing");
+		lenses.insert("c", 5);
+		lenses.insert("b", 2);
+		assert_eq!(lenses.to_string(), "[a 1] [c 5] [d 1] [b 2]");
+
+		// removing most lenses closes the gaps without changing the order of the rest
+		lenses.remove("a");
+		lenses.remove("c");
+		lenses.remove("d");
+		assert!(lenses.slots.len() <= 2 * lenses.positions.len());
+		assert_eq!(lenses, LensBox::from([Lens::new("b", 2)]));
+		lenses.remove("b");
+		assert!(lenses.is_empty());
+		assert_eq!(lenses, LensBox::default());
+	}
+
+	/// A long sequence of steps over `labels` labels that mixes inserts, replacements and removals
+	fn long_step_sequence(labels: &[String], count: usize) -> Vec<Step<'_>> {
+		let mut state = 0x2545_f491_4f6c_dd1du64;
+		(0..count)
+			.map(|_| {
+				// xorshift keeps the sequence reproducible without extra dependencies
+				state ^= state << 13;
+				state ^= state >> 7;
+				state ^= state << 17;
+				let label = &labels[(state % labels.len() as u64) as usize];
+				let operation = match (state >> 32) % 3 {
+					0 => StepOperation::Remove,
+					_ => StepOperation::Insert((state >> 40) as u8 % 9 + 1),
+				};
+				Step::new(label, operation).unwrap()
+			})
+			.collect()
+	}
+
+	fn labels(count: usize) -> Vec<String> {
+		(0..count).map(|i| format!("l{i}")).collect()
+	}
+
+	#[test]
+	fn test_long_step_sequence() {
+		let labels = labels(500);
+		let steps = long_step_sequence(&labels, 20_000);
+
+		// the previous layout, with a list per box and linear searches
+		let mut naive = vec![Vec::<Lens>::new(); 256];
+		for step in &steps {
+			let lenses = &mut naive[usize::from(step.label_hash)];
+			let position = lenses.iter().position(|lens| lens.label == step.label);
+			match (step.operation, position) {
+				(StepOperation::Remove, Some(i)) => {
+					lenses.remove(i);
+				}
+				(StepOperation::Remove, None) => {}
+				(StepOperation::Insert(focal_length), Some(i)) => {
+					lenses[i].focal_length = focal_length
+				}
+				(StepOperation::Insert(focal_length), None) => {
+					lenses.push(Lens::new(step.label, focal_length))
+				}
+			}
+		}
+
+		let boxes = steps.iter().copied().collect::<LensBoxes>();
+		for (box_index, lenses) in boxes.indexed() {
+			assert!(lenses.lenses().eq(naive[usize::from(box_index)].iter()));
+		}
+	}
+
+	/// Run with `cargo test --release -- --ignored --nocapture bench_`
+	#[test]
+	#[ignore]
+	fn bench_long_step_sequence() {
+		for label_count in [1_000, 100_000] {
+			let labels = labels(label_count);
+			let steps = long_step_sequence(&labels, 2_000_000);
+			let start = std::time::Instant::now();
+			let boxes = steps.iter().copied().collect::<LensBoxes>();
+			let elapsed = start.elapsed();
+			println!(
+				"{} steps over {label_count} labels: {elapsed:?} ({:?} per step), focusing power {}",
+				steps.len(),
+				elapsed / steps.len() as u32,
+				boxes.total_focusing_power().unwrap()
+			);
+		}
+	}
+
+	#[test]
+	fn test_step_operation_errors() {
+		let error = |s: &str| StepOperation::try_from(s).unwrap_err().to_string();
+		assert_eq!(
+			error("=x"),
+			"invalid focal length `x`: invalid digit found in string"
+		);
+		assert_eq!(error("€1"), "unknown operation `€`, expected `-` or `=`");
+		assert_eq!(error(""), "cannot parse `StepOperation` from empty string");
+	}
+
+	#[test]
+	fn test_repl() {
+		let input = "rn=1\ncm-\nqp=3\n\ncm=2\n";
+		let mut output = Vec::new();
+		let total = repl(input.as_bytes(), &mut output).unwrap();
+		assert_eq!(total, 7);
+		assert_eq!(
+			String::from_utf8(output).unwrap(),
+			[
+				"Box 0: [rn 1]",
+				"Total focusing power: 1",
+				"Box 0: [rn 1]",
+				"Total focusing power: 1",
+				"Box 1: [qp 3]",
+				"Total focusing power: 7",
+				"",
+			]
+			.join("\n")
+		);
+	}
+
+	#[test]
+	fn test_repl_invalid_steps() {
+		let input = "rn=1\nrn\nrn=x\n  rn-  \n";
+		let mut output = Vec::new();
+		let total = repl(input.as_bytes(), &mut output).unwrap();
+		assert_eq!(total, 0);
+		assert_eq!(
+			String::from_utf8(output).unwrap(),
+			[
+				"Box 0: [rn 1]",
+				"Total focusing power: 1",
+				"Invalid step `rn`: Step `rn` does not contain an operation (`-` or `=`)",
+				"Invalid step `rn=x`: invalid focal length `x`: invalid digit found in string",
+				"Box 0: (empty)",
+				"Total focusing power: 0",
+				"",
+			]
+			.join("\n")
+		);
+	}
 }
@@ -0,0 +1,304 @@
+//! The HASH algorithm with configurable parameters, and a search for labels with a given hash
+
+use std::collections::{HashSet, VecDeque};
+
+use anyhow::anyhow;
+
+/// HASH with `current = (current + byte) * multiplier % modulus` for every byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Hasher {
+	multiplier: u32,
+	modulus: u32,
+}
+
+impl Default for Hasher {
+	/// The Holiday ASCII String Helper from the puzzle, with one hash per box
+	fn default() -> Self {
+		Hasher {
+			multiplier: 17,
+			modulus: 256,
+		}
+	}
+}
+
+impl Hasher {
+	pub(super) fn new(multiplier: u32, modulus: u32) -> anyhow::Result<Self> {
+		if modulus == 0 {
+			return Err(anyhow!("the modulus of HASH must be positive"));
+		}
+		Ok(Hasher {
+			multiplier,
+			modulus,
+		})
+	}
+
+	fn step(&self, current: u32, byte: u8) -> u32 {
+		let modulus = u64::from(self.modulus);
+		// reducing the sum first keeps the product below `u32::MAX * u32::MAX`, which fits a `u64`
+		let sum = (u64::from(current) + u64::from(byte)) % modulus;
+		// this cannot truncate because the result is less than `modulus`
+		(sum * u64::from(self.multiplier) % modulus) as u32
+	}
+
+	/// Hashes raw bytes without checking that they are ASCII
+	pub(super) fn hash_bytes(&self, bytes: &[u8]) -> u32 {
+		if *self == Hasher::default() {
+			// multiplying and adding modulo 256 is what wrapping `u8` arithmetic does anyway
+			let hash = bytes.iter().fold(0u8, |current, &byte| {
+				current.wrapping_add(byte).wrapping_mul(17)
+			});
+			return u32::from(hash);
+		}
+		bytes
+			.iter()
+			.fold(0, |current, &byte| self.step(current, byte))
+	}
+
+	pub(super) fn hash(&self, input: &str) -> anyhow::Result<u32> {
+		if let Some(char) = input.chars().find(|char| !char.is_ascii()) {
+			return Err(anyhow!("cannot hash `{input}`: `{char}` is not ASCII"));
+		}
+		Ok(self.hash_bytes(input.as_bytes()))
+	}
+}
+
+/// Largest modulus to search preimages for, since the search keeps every state in memory
+const MAX_PREIMAGE_MODULUS: u32 = 1 << 20;
+
+/// Shortest ways from every hash state to a target hash, found by a breadth-first search
+/// backwards from the target. Takes memory proportional to the modulus, which is why it is
+/// limited to [`MAX_PREIMAGE_MODULUS`].
+pub(super) struct Preimages {
+	hasher: Hasher,
+	alphabet: Vec<u8>,
+	target: u32,
+	/// The byte to append next on a shortest way to the target, or `None` if the state is the
+	/// target itself or cannot reach it
+	next: Vec<Option<u8>>,
+}
+
+impl Preimages {
+	pub(super) fn new(hasher: Hasher, target: u32, alphabet: &str) -> anyhow::Result<Self> {
+		if hasher.modulus > MAX_PREIMAGE_MODULUS {
+			return Err(anyhow!(
+				"searching labels is only supported up to a modulus of {MAX_PREIMAGE_MODULUS}, got {}",
+				hasher.modulus
+			));
+		}
+		if target >= hasher.modulus {
+			return Err(anyhow!(
+				"no label hashes to {target} with a modulus of {}",
+				hasher.modulus
+			));
+		}
+		if !alphabet.is_ascii() {
+			return Err(anyhow!("the alphabet `{alphabet}` is not ASCII"));
+		}
+		let mut alphabet = alphabet.as_bytes().to_vec();
+		alphabet.sort_unstable();
+		alphabet.dedup();
+
+		let states = hasher.modulus as usize;
+		let mut predecessors = vec![Vec::new(); states];
+		for state in 0..hasher.modulus {
+			for &byte in &alphabet {
+				predecessors[hasher.step(state, byte) as usize].push((state, byte));
+			}
+		}
+
+		let mut reached = vec![false; states];
+		let mut next = vec![None; states];
+		reached[target as usize] = true;
+		let mut queue = VecDeque::from([target]);
+		while let Some(state) = queue.pop_front() {
+			for &(predecessor, byte) in &predecessors[state as usize] {
+				if !reached[predecessor as usize] {
+					reached[predecessor as usize] = true;
+					next[predecessor as usize] = Some(byte);
+					queue.push_back(predecessor);
+				}
+			}
+		}
+
+		Ok(Preimages {
+			hasher,
+			alphabet,
+			target,
+			next,
+		})
+	}
+
+	/// Returns the shortest bytes that lead from `state` to the target, which are empty if
+	/// `state` is the target
+	fn suffix(&self, mut state: u32) -> Option<Vec<u8>> {
+		let mut suffix = Vec::new();
+		while state != self.target {
+			let byte = self.next[state as usize]?;
+			suffix.push(byte);
+			state = self.hasher.step(state, byte);
+		}
+		Some(suffix)
+	}
+
+	/// Returns a shortest non-empty label with the target hash
+	pub(super) fn shortest(&self) -> Option<String> {
+		self.alphabet
+			.iter()
+			.filter_map(|&byte| {
+				let mut label = vec![byte];
+				label.extend(self.suffix(self.hasher.step(0, byte))?);
+				Some(label)
+			})
+			.min_by_key(|label| label.len())
+			// this cannot fail because the alphabet is ASCII
+			.map(|label| String::from_utf8(label).unwrap())
+	}
+
+	/// Returns `count` distinct labels with the target hash, or fewer if the search runs dry.
+	/// Every label is a prefix, tried shortest first, followed by the shortest way to the target.
+	pub(super) fn labels(&self, count: usize) -> Vec<String> {
+		let mut labels = Vec::with_capacity(count);
+		if self.shortest().is_none() {
+			return labels;
+		}
+		let mut seen = HashSet::new();
+		let mut prefixes = VecDeque::from([Vec::new()]);
+		// prefixes longer than a full cycle through every state cannot lead anywhere new
+		let max_prefix_len = self.hasher.modulus as usize;
+
+		while let Some(prefix) = prefixes.pop_front() {
+			if labels.len() == count {
+				break;
+			}
+			let state = self.hasher.hash_bytes(&prefix);
+			if let Some(suffix) = self.suffix(state) {
+				let label = [prefix.as_slice(), &suffix].concat();
+				if !label.is_empty() && seen.insert(label.clone()) {
+					// this cannot fail because the alphabet is ASCII
+					labels.push(String::from_utf8(label).unwrap());
+				}
+			}
+			if prefix.len() < max_prefix_len {
+				prefixes.extend(
+					self.alphabet
+						.iter()
+						.map(|&byte| [prefix.as_slice(), &[byte]].concat()),
+				);
+			}
+		}
+		labels
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_hash() {
+		let hasher = Hasher::default();
+		assert_eq!(hasher.hash("HASH").unwrap(), 52);
+		assert_eq!(hasher.hash("").unwrap(), 0);
+		assert!(hasher.hash("HÄSH").is_err());
+	}
+
+	#[test]
+	fn test_hash_bytes_matches_general_formula() {
+		let default = Hasher::default();
+		let general = |bytes: &[u8]| {
+			bytes
+				.iter()
+				.fold(0, |current, &byte| default.step(current, byte))
+		};
+		for input in ["HASH", "rn=1", "qp", "", "\u{7f}\u{7f}\u{7f}"] {
+			assert_eq!(
+				default.hash_bytes(input.as_bytes()),
+				general(input.as_bytes())
+			);
+		}
+		assert_eq!(default.hash_bytes(&[200, 255, 3]), general(&[200, 255, 3]));
+	}
+
+	#[test]
+	fn test_custom_parameters() {
+		let hasher = Hasher::new(31, 1_000_003).unwrap();
+		// ((72 * 31 + 73) * 31) % 1_000_003
+		assert_eq!(hasher.hash("HI").unwrap(), 71455);
+		assert!(Hasher::new(17, 0).is_err());
+	}
+
+	#[test]
+	fn test_large_parameters() {
+		let hasher = Hasher::new(u32::MAX, u32::MAX - 1).unwrap();
+		// (u32::MAX - 2 + 255) is 254 modulo u32::MAX - 1, and u32::MAX is 1
+		assert_eq!(hasher.step(u32::MAX - 2, 255), 254);
+		let hasher = Hasher::new(u32::MAX - 1, u32::MAX).unwrap();
+		let expected =
+			(u128::from(u32::MAX - 1) + 255) * u128::from(u32::MAX - 1) % u128::from(u32::MAX);
+		assert_eq!(u128::from(hasher.step(u32::MAX - 1, 255)), expected);
+	}
+
+	#[test]
+	fn test_shortest_preimage() {
+		let hasher = Hasher::default();
+		for target in [0, 1, 52, 255] {
+			let preimages = Preimages::new(hasher, target, "abcdefghijklmnopqrstuvwxyz").unwrap();
+			let label = preimages.shortest().unwrap();
+			assert_eq!(hasher.hash(&label).unwrap(), target);
+		}
+
+		// a single letter is the shortest label possible
+		let target = hasher.hash("q").unwrap();
+		let preimages = Preimages::new(hasher, target, "xyzq").unwrap();
+		assert_eq!(preimages.shortest().unwrap(), "q");
+
+		// with 17 and 256 coprime, an odd target cannot be reached with only even bytes
+		let preimages = Preimages::new(hasher, 1, "bdf").unwrap();
+		assert_eq!(preimages.shortest(), None);
+		assert!(Preimages::new(hasher, 256, "abc").is_err());
+
+		// this would not fit in memory
+		let hasher = Hasher::new(17, u32::MAX).unwrap();
+		assert!(Preimages::new(hasher, 0, "abc").is_err());
+		let hasher = Hasher::new(31, MAX_PREIMAGE_MODULUS).unwrap();
+		let label = Preimages::new(hasher, 12345, "abc")
+			.unwrap()
+			.shortest()
+			.unwrap();
+		assert_eq!(hasher.hash(&label).unwrap(), 12345);
+	}
+
+	#[test]
+	fn test_shortest_preimage_is_shortest() {
+		let hasher = Hasher::default();
+		let alphabet = "ab";
+		for target in 0..256 {
+			let preimages = Preimages::new(hasher, target, alphabet).unwrap();
+			// brute force over every label of length 1 to 8
+			let brute_force = (1..=8).find(|&len| {
+				(0..1u32 << len).any(|bits| {
+					let label = (0..len)
+						.map(|i| if bits >> i & 1 == 0 { b'a' } else { b'b' })
+						.collect::<Vec<_>>();
+					hasher.hash_bytes(&label) == target
+				})
+			});
+			let shortest = preimages.shortest().map(|label| label.len());
+			if let Some(len) = brute_force {
+				assert_eq!(shortest, Some(len));
+			} else {
+				assert!(shortest.is_none_or(|len| len > 8));
+			}
+		}
+	}
+
+	#[test]
+	fn test_colliding_labels() {
+		let hasher = Hasher::default();
+		let preimages = Preimages::new(hasher, 42, "abcdefghijklmnopqrstuvwxyz").unwrap();
+		let labels = preimages.labels(100);
+		assert_eq!(labels.len(), 100);
+		assert_eq!(labels.iter().collect::<HashSet<_>>().len(), 100);
+		assert!(labels.iter().all(|label| hasher.hash(label).unwrap() == 42));
+	}
+}
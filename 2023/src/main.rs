@@ -1,5 +1,7 @@
 // TODO: over-engineer even more using clap
 
+use std::str::FromStr;
+
 use seq_macro::seq;
 
 seq!(N in 01..=15 {
@@ -7,20 +9,8 @@ seq!(N in 01..=15 {
 });
 
 fn main() -> anyhow::Result<()> {
-	let mut day_input = String::new();
-	println!("Enter a day (1-24)");
-	std::io::stdin().read_line(&mut day_input)?;
-	let day: u8 = day_input.trim().parse()?;
-
-	let mut part_input = String::new();
-	println!("Enter a part (1)");
-	std::io::stdin().read_line(&mut part_input)?;
-	let part_input = part_input.trim();
-	let part: u8 = if !part_input.is_empty() {
-		part_input.parse()?
-	} else {
-		1
-	};
+	let day: u8 = prompt("Enter a day (1-24)")?.ok_or(anyhow::anyhow!("No day given"))?;
+	let part: u8 = prompt("Enter a part (1)")?.unwrap_or(1);
 	let part_two = part == 2;
 
 	let output = seq!(N in 01..=15 {
@@ -34,3 +24,33 @@ fn main() -> anyhow::Result<()> {
 
 	Ok(())
 }
+
+/// Asks for an option of a day, but only when running with `--configure`. Otherwise returns
+/// `None` without reading stdin, so that every option falls back to its default.
+fn option<T>(message: &str) -> anyhow::Result<Option<T>>
+where
+	T: FromStr,
+	T::Err: Into<anyhow::Error>,
+{
+	match std::env::args().skip(1).any(|arg| arg == "--configure") {
+		true => prompt(message),
+		false => Ok(None),
+	}
+}
+
+/// Asks for a value on stdin. Returns `None` if the input is empty, so that the caller can fall
+/// back to a default.
+fn prompt<T>(message: &str) -> anyhow::Result<Option<T>>
+where
+	T: FromStr,
+	T::Err: Into<anyhow::Error>,
+{
+	let mut input = String::new();
+	println!("{}", message);
+	std::io::stdin().read_line(&mut input)?;
+	let input = input.trim();
+	if input.is_empty() {
+		return Ok(None);
+	}
+	input.parse().map(Some).map_err(Into::into)
+}